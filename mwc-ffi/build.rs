@@ -0,0 +1,17 @@
+// Generates the C header into OUT_DIR, where tests/c_api.rs checks that the
+// copy in include/mwc.h is in step with the exported functions.
+fn main() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    println!("cargo::rerun-if-changed=src/lib.rs");
+    println!("cargo::rerun-if-changed=cbindgen.toml");
+
+    let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir))
+        .expect("cbindgen.toml is valid");
+    cbindgen::Builder::new()
+        .with_crate(&crate_dir)
+        .with_config(config)
+        .generate()
+        .expect("the C header can be generated")
+        .write_to_file(format!("{}/mwc.h", out_dir));
+}
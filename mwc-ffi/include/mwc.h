@@ -0,0 +1,95 @@
+/* Generated by cbindgen from mwc-ffi/src/lib.rs; do not edit. */
+
+#ifndef MWC_H
+#define MWC_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Counts data pushed in chunks of any size, from `mwc_counter_new`.
+ */
+typedef struct MwcCounter MwcCounter;
+
+/**
+ * Counts of one input.
+ */
+typedef struct MwcCounts {
+  size_t lines;
+  size_t words;
+  size_t bytes;
+  size_t chars;
+  size_t max_line_length;
+} MwcCounts;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Counts the `len` bytes at `data` into `*out`.
+ *
+ * Returns 0, or -1 if `out` is null or `data` is null while `len` isn't 0.
+ *
+ * # Safety
+ *
+ * `data` must point to `len` readable bytes and `out` to a writable `MwcCounts`.
+ */
+int mwc_count_buffer(const uint8_t *data, size_t len, struct MwcCounts *out);
+
+#if defined(__unix__)
+/**
+ * Reads `fd` to its end and counts what was read into `*out`. The descriptor
+ * is left open.
+ *
+ * Returns 0, -1 if `fd` is negative or `out` is null, or the `errno` of a
+ * failed read. Like mwc, whatever was read before a failure is still counted
+ * into `*out`.
+ *
+ * # Safety
+ *
+ * `fd` must be an open descriptor and `out` must point to a writable `MwcCounts`.
+ */
+int mwc_count_fd(int fd, struct MwcCounts *out);
+#endif
+
+/**
+ * Starts counting data passed to `mwc_counter_update`. Release the counter
+ * with `mwc_counter_finish`.
+ */
+struct MwcCounter *mwc_counter_new(void);
+
+/**
+ * Counts the next `len` bytes at `data`. Chunks may split lines, words and
+ * UTF-8 sequences anywhere.
+ *
+ * Returns 0, or -1 if `counter` is null or `data` is null while `len` isn't 0.
+ *
+ * # Safety
+ *
+ * `counter` must come from `mwc_counter_new` and not be finished yet, and
+ * `data` must point to `len` readable bytes.
+ */
+int mwc_counter_update(struct MwcCounter *counter, const uint8_t *data, size_t len);
+
+/**
+ * Writes the counts of everything passed to `counter` into `*out`, unless
+ * `out` is null, and frees `counter`.
+ *
+ * Returns 0, or -1 if `counter` is null.
+ *
+ * # Safety
+ *
+ * `counter` must come from `mwc_counter_new` and is invalid afterwards; `out`
+ * must be null or point to a writable `MwcCounts`.
+ */
+int mwc_counter_finish(struct MwcCounter *counter, struct MwcCounts *out);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* MWC_H */
@@ -0,0 +1,156 @@
+//! C ABI for mwc's default counts: lines, words, bytes, characters and the
+//! maximum line length, exactly as `mwc -lwcmL` reports them.
+//!
+//! Every build generates the C header into `OUT_DIR`; `tests/c_api.rs`
+//! checks that the copy in `include/mwc.h` matches it, and updates that copy
+//! when `MWC_UPDATE_HEADER` is set.
+
+use std::{ffi::c_int, sync::LazyLock};
+
+use mwc::{CountOptions, FileCounts};
+
+static OPTIONS: LazyLock<CountOptions> = LazyLock::new(CountOptions::default);
+
+/// Counts of one input.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MwcCounts {
+    pub lines: usize,
+    pub words: usize,
+    pub bytes: usize,
+    pub chars: usize,
+    pub max_line_length: usize,
+}
+
+impl From<&FileCounts> for MwcCounts {
+    fn from(counts: &FileCounts) -> Self {
+        Self {
+            lines: counts.lines,
+            words: counts.words,
+            bytes: counts.bytes,
+            chars: counts.chars,
+            max_line_length: counts.max_line_length,
+        }
+    }
+}
+
+/// Counts data pushed in chunks of any size, from `mwc_counter_new`.
+pub struct MwcCounter(mwc::Counter<'static>);
+
+// `len` bytes at `data`, where `data` may only be null if `len` is 0.
+unsafe fn bytes<'a>(data: *const u8, len: usize) -> Option<&'a [u8]> {
+    if len == 0 {
+        Some(&[])
+    } else if data.is_null() {
+        None
+    } else {
+        Some(unsafe { std::slice::from_raw_parts(data, len) })
+    }
+}
+
+/// Counts the `len` bytes at `data` into `*out`.
+///
+/// Returns 0, or -1 if `out` is null or `data` is null while `len` isn't 0.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes and `out` to a writable `MwcCounts`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mwc_count_buffer(
+    data: *const u8,
+    len: usize,
+    out: *mut MwcCounts,
+) -> c_int {
+    let Some(data) = (unsafe { bytes(data, len) }) else {
+        return -1;
+    };
+    if out.is_null() {
+        return -1;
+    }
+    let (counts, _) = FileCounts::from_reader(data, &OPTIONS);
+    unsafe { out.write((&counts).into()) };
+    0
+}
+
+/// Reads `fd` to its end and counts what was read into `*out`. The descriptor
+/// is left open.
+///
+/// Returns 0, -1 if `fd` is negative or `out` is null, or the `errno` of a
+/// failed read. Like mwc, whatever was read before a failure is still counted
+/// into `*out`.
+///
+/// # Safety
+///
+/// `fd` must be an open descriptor and `out` must point to a writable `MwcCounts`.
+#[cfg(unix)]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mwc_count_fd(fd: c_int, out: *mut MwcCounts) -> c_int {
+    use std::{fs::File, io::BufReader, mem::ManuallyDrop, os::fd::FromRawFd};
+
+    if fd < 0 || out.is_null() {
+        return -1;
+    }
+    // the caller owns fd, so it mustn't be closed on drop
+    let file = ManuallyDrop::new(unsafe { File::from_raw_fd(fd) });
+    let (counts, result) = FileCounts::from_reader(BufReader::new(&*file), &OPTIONS);
+    unsafe { out.write((&counts).into()) };
+    match result {
+        Ok(()) => 0,
+        Err(e) => e.raw_os_error().unwrap_or(-1),
+    }
+}
+
+/// Starts counting data passed to `mwc_counter_update`. Release the counter
+/// with `mwc_counter_finish`.
+#[unsafe(no_mangle)]
+pub extern "C" fn mwc_counter_new() -> *mut MwcCounter {
+    Box::into_raw(Box::new(MwcCounter(mwc::Counter::new(&OPTIONS))))
+}
+
+/// Counts the next `len` bytes at `data`. Chunks may split lines, words and
+/// UTF-8 sequences anywhere.
+///
+/// Returns 0, or -1 if `counter` is null or `data` is null while `len` isn't 0.
+///
+/// # Safety
+///
+/// `counter` must come from `mwc_counter_new` and not be finished yet, and
+/// `data` must point to `len` readable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mwc_counter_update(
+    counter: *mut MwcCounter,
+    data: *const u8,
+    len: usize,
+) -> c_int {
+    let (Some(counter), Some(data)) = (unsafe { counter.as_mut() }, unsafe { bytes(data, len) })
+    else {
+        return -1;
+    };
+    counter.0.update(data);
+    0
+}
+
+/// Writes the counts of everything passed to `counter` into `*out`, unless
+/// `out` is null, and frees `counter`.
+///
+/// Returns 0, or -1 if `counter` is null.
+///
+/// # Safety
+///
+/// `counter` must come from `mwc_counter_new` and is invalid afterwards; `out`
+/// must be null or point to a writable `MwcCounts`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mwc_counter_finish(
+    counter: *mut MwcCounter,
+    out: *mut MwcCounts,
+) -> c_int {
+    if counter.is_null() {
+        return -1;
+    }
+    let counter = unsafe { Box::from_raw(counter) };
+    let counts = counter.0.finish();
+    if !out.is_null() {
+        unsafe { out.write((&counts).into()) };
+    }
+    0
+}
@@ -0,0 +1,59 @@
+/* Exercises the mwc C ABI; the first argument is a file to count by descriptor. */
+#include <fcntl.h>
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+
+#include "mwc.h"
+
+static int failures = 0;
+
+static void expect(const char *what, struct MwcCounts got, struct MwcCounts want) {
+  if (memcmp(&got, &want, sizeof got) != 0) {
+    fprintf(stderr, "%s: got %zu %zu %zu %zu %zu, want %zu %zu %zu %zu %zu\n", what,
+            got.lines, got.words, got.bytes, got.chars, got.max_line_length,
+            want.lines, want.words, want.bytes, want.chars, want.max_line_length);
+    failures++;
+  }
+}
+
+int main(int argc, char **argv) {
+  const char *text = "h\xc3\xa9llo w\xc3\xb6rld\nsecond line here\nno newline";
+  struct MwcCounts want = {3, 7, strlen(text), 39, 16};
+  struct MwcCounts got;
+
+  if (mwc_count_buffer((const uint8_t *)text, strlen(text), &got) != 0) {
+    fprintf(stderr, "mwc_count_buffer failed\n");
+    return 1;
+  }
+  expect("buffer", got, want);
+
+  /* one byte at a time splits every word and UTF-8 sequence */
+  struct MwcCounter *counter = mwc_counter_new();
+  for (size_t i = 0; i < strlen(text); i++) {
+    mwc_counter_update(counter, (const uint8_t *)text + i, 1);
+  }
+  mwc_counter_finish(counter, &got);
+  expect("counter", got, want);
+
+  if (argc > 1) {
+    int fd = open(argv[1], O_RDONLY);
+    if (fd < 0 || mwc_count_fd(fd, &got) != 0) {
+      fprintf(stderr, "mwc_count_fd failed\n");
+      return 1;
+    }
+    expect("fd", got, want);
+    /* the descriptor stays open */
+    if (close(fd) != 0) {
+      fprintf(stderr, "mwc_count_fd closed its descriptor\n");
+      failures++;
+    }
+  }
+
+  if (mwc_count_buffer(NULL, 1, &got) != -1 || mwc_counter_finish(NULL, NULL) != -1) {
+    fprintf(stderr, "null pointers weren't rejected\n");
+    failures++;
+  }
+
+  return failures == 0 ? 0 : 1;
+}
@@ -0,0 +1,56 @@
+use std::{path::PathBuf, process::Command};
+
+use assert_fs::prelude::*;
+
+// cargo builds the static library into the deps directory holding this test.
+#[cfg(unix)]
+fn deps_dir() -> PathBuf {
+    let exe = std::env::current_exe().unwrap();
+    exe.parent().unwrap().to_path_buf()
+}
+
+#[test]
+fn test_header_up_to_date() {
+    let generated = concat!(env!("OUT_DIR"), "/mwc.h");
+    let checked_in = concat!(env!("CARGO_MANIFEST_DIR"), "/include/mwc.h");
+    if std::env::var_os("MWC_UPDATE_HEADER").is_some() {
+        std::fs::copy(generated, checked_in).unwrap();
+    }
+    let read = |path| std::fs::read_to_string(path).unwrap();
+    assert!(
+        read(generated).lines().eq(read(checked_in).lines()),
+        "include/mwc.h is out of date; run MWC_UPDATE_HEADER=1 cargo test -p mwc-ffi"
+    );
+}
+
+// Links the way the static library needs to on Linux and the BSDs
+#[cfg(unix)]
+#[test]
+fn test_c_program() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("input.txt")
+        .write_binary(b"h\xc3\xa9llo w\xc3\xb6rld\nsecond line here\nno newline")
+        .unwrap();
+    let program = temp.child("test_counts");
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+
+    let status = Command::new(std::env::var("CC").unwrap_or_else(|_| "cc".into()))
+        .arg(format!("{}/tests/c/test_counts.c", manifest_dir))
+        .arg(format!("-I{}/include", manifest_dir))
+        .arg(deps_dir().join("libmwc_ffi.a"))
+        .args(["-lpthread", "-ldl", "-lm", "-o"])
+        .arg(program.path())
+        .status()
+        .expect("a C compiler is needed to test the C API");
+    assert!(status.success(), "test_counts.c didn't compile");
+
+    let output = Command::new(program.path())
+        .arg(temp.child("input.txt").path())
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,357 @@
+//! Python bindings for mwc's counts, as the `mwc` extension module.
+//!
+//! Counting goes through the mwc library itself, so the numbers are the ones
+//! the command line prints.
+
+use std::{borrow::Cow, fs::File, io::BufReader, path::PathBuf};
+
+use pyo3::{
+    exceptions::PyValueError,
+    prelude::*,
+    types::{PyDict, PyString},
+};
+
+// Bytes requested from a file-like object at a time.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// What gets counted, like the counting options of the mwc command.
+#[pyclass(module = "mwc", frozen)]
+struct CountOptions(mwc::CountOptions);
+
+#[pymethods]
+impl CountOptions {
+    #[new]
+    #[pyo3(signature = (
+        *,
+        patterns = Vec::new(),
+        fixed_strings = false,
+        match_mode = "lines",
+        record_separator = None,
+        null_data = false,
+        word_chars = None,
+        word_regex = None,
+        line_endings = false,
+        prose = false,
+        tokens = None,
+        fields = false,
+        delimiter = None,
+        rfc4180 = false,
+        skip_lines = 0,
+        max_records = None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        patterns: Vec<String>,
+        fixed_strings: bool,
+        match_mode: &str,
+        record_separator: Option<Vec<u8>>,
+        null_data: bool,
+        word_chars: Option<String>,
+        word_regex: Option<String>,
+        line_endings: bool,
+        prose: bool,
+        tokens: Option<PathBuf>,
+        fields: bool,
+        delimiter: Option<Vec<u8>>,
+        rfc4180: bool,
+        skip_lines: usize,
+        max_records: Option<usize>,
+    ) -> PyResult<Self> {
+        let match_mode = match match_mode {
+            "lines" => mwc::MatchMode::Lines,
+            "occurrences" => mwc::MatchMode::Occurrences,
+            other => {
+                return Err(PyValueError::new_err(format!(
+                    "match_mode must be 'lines' or 'occurrences', not '{}'",
+                    other
+                )));
+            }
+        };
+        let delimiter = match delimiter.as_deref() {
+            None => None,
+            Some(&[delimiter]) => Some(delimiter),
+            Some(_) => return Err(PyValueError::new_err("delimiter must be a single byte")),
+        };
+        mwc::CountOptions::builder()
+            .patterns(patterns)
+            .fixed_strings(fixed_strings)
+            .match_mode(match_mode)
+            .word_chars(word_chars)
+            .word_regex(word_regex)
+            .null_data(null_data)
+            .record_separator(record_separator)
+            .line_endings(line_endings)
+            .prose(prose)
+            .tokens(tokens)
+            .fields(fields)
+            .delimiter(delimiter)
+            .rfc4180(rfc4180)
+            .skip_records(skip_lines)
+            .max_records(max_records)
+            .build()
+            .map(Self)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+}
+
+/// Line ending diagnostics, only counted with `CountOptions(line_endings=True)`.
+#[pyclass(module = "mwc", frozen, eq, get_all, skip_from_py_object)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LineEndings {
+    lf: usize,
+    crlf: usize,
+    cr: usize,
+    unterminated: usize,
+    /// "none", "utf-8", "utf-16le" or "utf-16be"
+    bom: String,
+    invalid_utf8: usize,
+    nul: usize,
+}
+
+#[pymethods]
+impl LineEndings {
+    fn __repr__(&self) -> String {
+        format!(
+            "LineEndings(lf={}, crlf={}, cr={}, unterminated={}, bom={:?}, invalid_utf8={}, nul={})",
+            self.lf, self.crlf, self.cr, self.unterminated, self.bom, self.invalid_utf8, self.nul
+        )
+    }
+}
+
+/// Field counts of delimited data, only counted with `CountOptions(fields=True)`.
+#[pyclass(module = "mwc", frozen, eq, get_all, skip_from_py_object)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Fields {
+    records: usize,
+    min_fields: usize,
+    max_fields: usize,
+    /// Fields in the first record, taken to be the header
+    header_fields: Option<usize>,
+    /// Records whose field count differs from the header's
+    mismatched: usize,
+}
+
+#[pymethods]
+impl Fields {
+    fn __repr__(&self) -> String {
+        let header_fields = match self.header_fields {
+            Some(n) => n.to_string(),
+            None => "None".into(),
+        };
+        format!(
+            "Fields(records={}, min_fields={}, max_fields={}, header_fields={}, mismatched={})",
+            self.records, self.min_fields, self.max_fields, header_fields, self.mismatched
+        )
+    }
+}
+
+/// Counts of one input, with the fields of a `mwc --format=json` entry.
+#[pyclass(module = "mwc", frozen, eq, get_all, skip_from_py_object)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FileCounts {
+    lines: usize,
+    words: usize,
+    bytes: usize,
+    chars: usize,
+    max_line_length: usize,
+    /// One count per pattern, in order
+    matches: Vec<usize>,
+    /// 0 unless counted with `prose=True`
+    paragraphs: usize,
+    sentences: usize,
+    /// 0 unless counted with `tokens=...`
+    tokens: usize,
+    line_endings: Option<LineEndings>,
+    fields: Option<Fields>,
+}
+
+impl From<mwc::FileCounts> for FileCounts {
+    fn from(counts: mwc::FileCounts) -> Self {
+        let line_endings = (!counts.line_endings.is_unscanned()).then(|| {
+            let scanned = &counts.line_endings;
+            LineEndings {
+                lf: scanned.lf,
+                crlf: scanned.crlf,
+                cr: scanned.cr,
+                unterminated: scanned.unterminated,
+                bom: scanned.bom.map(|bom| bom.to_string()).unwrap_or_default(),
+                invalid_utf8: scanned.invalid_utf8,
+                nul: scanned.nul,
+            }
+        });
+        let fields = (!counts.fields.is_unscanned()).then_some(Fields {
+            records: counts.fields.records,
+            min_fields: counts.fields.min_fields,
+            max_fields: counts.fields.max_fields,
+            header_fields: counts.fields.header_fields,
+            mismatched: counts.fields.mismatched,
+        });
+        Self {
+            lines: counts.lines,
+            words: counts.words,
+            bytes: counts.bytes,
+            chars: counts.chars,
+            max_line_length: counts.max_line_length,
+            matches: counts.matches,
+            paragraphs: counts.paragraphs,
+            sentences: counts.sentences,
+            tokens: counts.tokens,
+            line_endings,
+            fields,
+        }
+    }
+}
+
+#[pymethods]
+impl FileCounts {
+    /// Average words per sentence, or None without sentences.
+    #[getter]
+    fn words_per_sentence(&self) -> Option<f64> {
+        (self.sentences > 0).then(|| self.words as f64 / self.sentences as f64)
+    }
+
+    /// The counts as a dict, like `dataclasses.asdict`.
+    fn asdict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("lines", self.lines)?;
+        dict.set_item("words", self.words)?;
+        dict.set_item("bytes", self.bytes)?;
+        dict.set_item("chars", self.chars)?;
+        dict.set_item("max_line_length", self.max_line_length)?;
+        dict.set_item("matches", &self.matches)?;
+        dict.set_item("paragraphs", self.paragraphs)?;
+        dict.set_item("sentences", self.sentences)?;
+        dict.set_item("tokens", self.tokens)?;
+        let line_endings = match self.line_endings {
+            Some(ref line_endings) => {
+                let dict = PyDict::new(py);
+                dict.set_item("lf", line_endings.lf)?;
+                dict.set_item("crlf", line_endings.crlf)?;
+                dict.set_item("cr", line_endings.cr)?;
+                dict.set_item("unterminated", line_endings.unterminated)?;
+                dict.set_item("bom", &line_endings.bom)?;
+                dict.set_item("invalid_utf8", line_endings.invalid_utf8)?;
+                dict.set_item("nul", line_endings.nul)?;
+                Some(dict)
+            }
+            None => None,
+        };
+        dict.set_item("line_endings", line_endings)?;
+        let fields = match self.fields {
+            Some(ref fields) => {
+                let dict = PyDict::new(py);
+                dict.set_item("records", fields.records)?;
+                dict.set_item("min_fields", fields.min_fields)?;
+                dict.set_item("max_fields", fields.max_fields)?;
+                dict.set_item("header_fields", fields.header_fields)?;
+                dict.set_item("mismatched", fields.mismatched)?;
+                Some(dict)
+            }
+            None => None,
+        };
+        dict.set_item("fields", fields)?;
+        Ok(dict)
+    }
+
+    fn __repr__(&self) -> String {
+        let mut repr = format!(
+            "FileCounts(lines={}, words={}, bytes={}, chars={}, max_line_length={}, matches={:?}, paragraphs={}, sentences={}, tokens={}, line_endings=",
+            self.lines,
+            self.words,
+            self.bytes,
+            self.chars,
+            self.max_line_length,
+            self.matches,
+            self.paragraphs,
+            self.sentences,
+            self.tokens
+        );
+        match self.line_endings {
+            Some(ref line_endings) => repr.push_str(&line_endings.__repr__()),
+            None => repr.push_str("None"),
+        }
+        repr.push_str(", fields=");
+        match self.fields {
+            Some(ref fields) => repr.push_str(&fields.__repr__()),
+            None => repr.push_str("None"),
+        }
+        repr.push(')');
+        repr
+    }
+}
+
+/// Counts the file at `path`. Raises OSError if it can't be read.
+#[pyfunction]
+#[pyo3(signature = (path, options = None))]
+fn count_path(
+    py: Python<'_>,
+    path: PathBuf,
+    options: Option<&Bound<'_, CountOptions>>,
+) -> PyResult<FileCounts> {
+    let default = mwc::CountOptions::default();
+    let options = options.map_or(&default, |options| &options.get().0);
+    let (counts, result) = py.detach(|| {
+        let file = File::open(&path)?;
+        let (counts, result) = mwc::FileCounts::from_reader(BufReader::new(file), options);
+        Ok::<_, std::io::Error>((counts, result))
+    })?;
+    result?;
+    Ok(counts.into())
+}
+
+/// Counts `data`, a bytes-like object.
+#[pyfunction]
+#[pyo3(signature = (data, options = None))]
+fn count_bytes(
+    py: Python<'_>,
+    data: Cow<'_, [u8]>,
+    options: Option<&Bound<'_, CountOptions>>,
+) -> FileCounts {
+    let default = mwc::CountOptions::default();
+    let options = options.map_or(&default, |options| &options.get().0);
+    let (counts, _) = py.detach(|| mwc::FileCounts::from_reader(&*data, options));
+    counts.into()
+}
+
+/// Reads `file` to its end and counts what it returned. Text files are
+/// counted as UTF-8.
+#[pyfunction]
+#[pyo3(signature = (file, options = None))]
+fn count_file(
+    file: &Bound<'_, PyAny>,
+    options: Option<&Bound<'_, CountOptions>>,
+) -> PyResult<FileCounts> {
+    let default = mwc::CountOptions::default();
+    let options = options.map_or(&default, |options| &options.get().0);
+    let mut counter = mwc::Counter::new(options);
+    loop {
+        let chunk = file.call_method1("read", (CHUNK_SIZE,))?;
+        if let Ok(text) = chunk.cast::<PyString>() {
+            let text = text.to_cow()?;
+            if text.is_empty() {
+                break;
+            }
+            counter.update(text.as_bytes());
+        } else {
+            let bytes: Cow<'_, [u8]> = chunk.extract()?;
+            if bytes.is_empty() {
+                break;
+            }
+            counter.update(&bytes);
+        }
+    }
+    Ok(counter.finish().into())
+}
+
+#[pymodule]
+#[pyo3(name = "mwc")]
+fn mwc_module(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<CountOptions>()?;
+    module.add_class::<FileCounts>()?;
+    module.add_class::<LineEndings>()?;
+    module.add_class::<Fields>()?;
+    module.add_function(wrap_pyfunction!(count_path, module)?)?;
+    module.add_function(wrap_pyfunction!(count_bytes, module)?)?;
+    module.add_function(wrap_pyfunction!(count_file, module)?)?;
+    Ok(())
+}
@@ -0,0 +1,37 @@
+"""Makes the extension importable for pytest without installing it.
+
+An installed `mwc` (e.g. from `maturin develop`) is used if there is one;
+otherwise the library built by `cargo build -p mwc-py` is loaded from the
+workspace's target directory, or from $MWC_PY_LIB.
+"""
+
+import importlib.machinery
+import importlib.util
+import os
+import pathlib
+import sys
+
+WORKSPACE = pathlib.Path(__file__).resolve().parents[2]
+
+
+def _load_built_module():
+    candidates = [os.environ.get("MWC_PY_LIB")] + [
+        str(WORKSPACE / "target" / profile / name)
+        for profile in ("debug", "release")
+        for name in ("libmwc_py.so", "libmwc_py.dylib", "mwc_py.dll")
+    ]
+    for candidate in filter(None, candidates):
+        if os.path.exists(candidate):
+            loader = importlib.machinery.ExtensionFileLoader("mwc", candidate)
+            spec = importlib.util.spec_from_file_location("mwc", candidate, loader=loader)
+            module = importlib.util.module_from_spec(spec)
+            loader.exec_module(module)
+            sys.modules["mwc"] = module
+            return
+    raise ImportError("build the extension first: cargo build -p mwc-py")
+
+
+try:
+    import mwc  # noqa: F401
+except ImportError:
+    _load_built_module()
@@ -0,0 +1,215 @@
+"""The bindings against the inputs and expectations of mwc/tests/integration_tests.rs."""
+
+import io
+import json
+import os
+import pathlib
+import shutil
+import subprocess
+
+import pytest
+
+import mwc
+
+WORKSPACE = pathlib.Path(__file__).resolve().parents[2]
+FIXTURES = WORKSPACE / "mwc" / "tests" / "fixtures"
+TIKTOKEN_VOCAB = FIXTURES / "tiny.tiktoken"
+TOKENIZER_JSON = FIXTURES / "tiny_tokenizer.json"
+
+
+def test_default_counts():
+    counts = mwc.count_bytes(b"hello world\n")
+    assert (counts.lines, counts.words, counts.bytes, counts.chars) == (1, 2, 12, 12)
+    assert counts.max_line_length == 11
+    assert counts.matches == []
+    assert counts.line_endings is None
+
+
+def test_count_path(tmp_path):
+    path = tmp_path / "test.txt"
+    path.write_text("héllo wörld\nsecond line here\nno newline")
+    counts = mwc.count_path(path)
+    assert counts == mwc.count_bytes(path.read_bytes())
+    # the unterminated last line counts as a line, like the CLI
+    assert (counts.lines, counts.words, counts.bytes, counts.chars) == (3, 7, 41, 39)
+
+
+def test_count_path_missing(tmp_path):
+    with pytest.raises(FileNotFoundError):
+        mwc.count_path(tmp_path / "nonexistent.txt")
+
+
+def test_count_file(tmp_path):
+    path = tmp_path / "test.txt"
+    path.write_text("héllo wörld\n" * 10_000)
+    expected = mwc.count_path(path)
+    with open(path, "rb") as f:
+        assert mwc.count_file(f) == expected
+    with open(path, encoding="utf-8") as f:
+        assert mwc.count_file(f) == expected
+    assert mwc.count_file(io.BytesIO(path.read_bytes())) == expected
+
+
+def test_count_matches_lines():
+    options = mwc.CountOptions(patterns=["error"])
+    counts = mwc.count_bytes(b"error: one\nok\nerror: two error\n", options)
+    assert counts.lines == 3
+    assert counts.matches == [2]
+
+
+def test_count_matches_occurrences():
+    options = mwc.CountOptions(patterns=["err(or)?", "o"], match_mode="occurrences")
+    counts = mwc.count_bytes(b"error: one\nok\nerror: two error\n", options)
+    assert counts.matches == [3, 6]
+
+
+def test_count_matches_fixed_strings():
+    options = mwc.CountOptions(patterns=["a.b"], fixed_strings=True)
+    assert mwc.count_bytes(b"a.b\naxb\n", options).matches == [1]
+
+
+def test_count_matches_invalid_pattern():
+    with pytest.raises(ValueError, match="invalid pattern"):
+        mwc.CountOptions(patterns=["(unclosed"])
+
+
+def test_null_data_records():
+    counts = mwc.count_bytes(b"a/b\0c\0longer/path\0", mwc.CountOptions(null_data=True))
+    assert (counts.lines, counts.max_line_length) == (3, 11)
+
+
+def test_record_separator_multibyte():
+    options = mwc.CountOptions(record_separator=b"\r\n")
+    # "two\rthree" is a single record; the trailing "four" is unterminated
+    assert mwc.count_bytes(b"one\r\ntwo\rthree\r\nfour", options).lines == 3
+
+
+def test_record_separator_conflicts_with_null_data():
+    with pytest.raises(ValueError):
+        mwc.CountOptions(null_data=True, record_separator=b";")
+
+
+def test_word_definitions():
+    text = b"snake_case and kebab-case\n"
+    assert mwc.count_bytes(text, mwc.CountOptions(word_chars="[:alnum:]_")).words == 4
+    assert mwc.count_bytes(text, mwc.CountOptions(word_regex=r"[a-z]+-[a-z]+")).words == 1
+    with pytest.raises(ValueError):
+        mwc.CountOptions(word_chars="a", word_regex="b")
+    with pytest.raises(ValueError, match="invalid word definition"):
+        mwc.CountOptions(word_regex="(unclosed")
+
+
+def test_fields():
+    options = mwc.CountOptions(fields=True, rfc4180=True)
+    fields = mwc.count_bytes(b'a,b,c\n1,"2,3",4\n5,6\n', options).fields
+    assert (fields.records, fields.min_fields, fields.max_fields) == (3, 2, 3)
+    assert (fields.header_fields, fields.mismatched) == (3, 1)
+    tabs = mwc.CountOptions(fields=True, delimiter=b"\t")
+    assert mwc.count_bytes(b"a\tb,c\n", tabs).fields.max_fields == 2
+    assert mwc.count_bytes(b"a,b\n").fields is None
+    with pytest.raises(ValueError):
+        mwc.CountOptions(fields=True, delimiter=b"::")
+    with pytest.raises(ValueError):
+        mwc.CountOptions(rfc4180=True)
+
+
+def test_skip_lines_max_records():
+    options = mwc.CountOptions(skip_lines=1, max_records=2)
+    counts = mwc.count_bytes(b"header\none\ntwo\nthree\n", options)
+    assert (counts.lines, counts.words) == (2, 2)
+
+
+def test_line_endings_report():
+    options = mwc.CountOptions(line_endings=True)
+    unix = mwc.count_bytes(b"one\ntwo\n", options).line_endings
+    dos = mwc.count_bytes(b"\xef\xbb\xbfone\r\ntwo\rthree", options).line_endings
+    assert (unix.lf, unix.crlf, unix.cr, unix.unterminated, unix.bom) == (2, 0, 0, 0, "none")
+    assert (dos.lf, dos.crlf, dos.cr, dos.unterminated, dos.bom) == (0, 1, 1, 1, "utf-8")
+    assert (dos.invalid_utf8, dos.nul) == (0, 0)
+
+
+def test_paragraphs_and_sentences():
+    text = (
+        b"Mr. Smith went to Washington. He stayed\n"
+        b"for a week!\n"
+        b"\n"
+        b"\n"
+        b"Was it worth it? Perhaps. The U.S. capital is busy.\n"
+    )
+    counts = mwc.count_bytes(text, mwc.CountOptions(prose=True))
+    assert (counts.words, counts.paragraphs, counts.sentences) == (20, 2, 5)
+    assert counts.words_per_sentence == 4.0
+    assert mwc.count_bytes(b"").words_per_sentence is None
+
+
+def test_tokens_tiktoken():
+    options = mwc.CountOptions(tokens=TIKTOKEN_VOCAB)
+    counts = mwc.count_bytes(b"the cat sat\n\nthe dog\n", options)
+    assert (counts.words, counts.tokens) == (5, 11)
+
+
+def test_tokens_tokenizer_json():
+    options = mwc.CountOptions(tokens=str(TOKENIZER_JSON))
+    assert mwc.count_bytes(b"the cat sat", options).tokens == 3
+    assert mwc.count_bytes(b"the dog sat", options).tokens == 6
+
+
+def test_tokens_invalid_vocabulary(tmp_path):
+    vocab = tmp_path / "broken.tiktoken"
+    vocab.write_text("not base64 at all\n")
+    with pytest.raises(ValueError, match="line 1: expected BASE64 RANK"):
+        mwc.CountOptions(tokens=vocab)
+
+
+def test_asdict_and_repr():
+    counts = mwc.count_bytes(b"a b\n", mwc.CountOptions(patterns=["a"]))
+    assert counts.asdict() == {
+        "lines": 1,
+        "words": 2,
+        "bytes": 4,
+        "chars": 4,
+        "max_line_length": 3,
+        "matches": [1],
+        "paragraphs": 0,
+        "sentences": 0,
+        "tokens": 0,
+        "line_endings": None,
+        "fields": None,
+    }
+    assert repr(counts).startswith("FileCounts(lines=1, words=2, bytes=4,")
+
+
+def _mwc_binary():
+    built = WORKSPACE / "target" / "debug" / "mwc"
+    return os.environ.get("MWC_BIN") or (str(built) if built.exists() else shutil.which("mwc"))
+
+
+@pytest.mark.skipif(_mwc_binary() is None, reason="no mwc binary; run cargo build -p mwc")
+def test_agrees_with_cli(tmp_path):
+    path = tmp_path / "doc.txt"
+    path.write_bytes(b"\xef\xbb\xbfTODO: one\r\nwide \xe4\xb8\x96\xe7\x95\x8c. Done!\r\n\r\nlast TODO")
+    report = subprocess.run(
+        [
+            _mwc_binary(),
+            "--format=json",
+            "-e",
+            "TODO",
+            "--line-endings",
+            "--sentences",
+            "--fields",
+            path,
+        ],
+        check=True,
+        capture_output=True,
+    )
+    cli = json.loads(report.stdout)["files"][0]
+
+    options = mwc.CountOptions(patterns=["TODO"], line_endings=True, prose=True, fields=True)
+    counts = mwc.count_path(path, options).asdict()
+    for field in ("lines", "words", "bytes", "chars", "max_line_length", "matches", "sentences"):
+        assert counts[field] == cli[field], field
+    for field in ("lf", "crlf", "cr", "unterminated", "invalid_utf8", "nul"):
+        assert counts["line_endings"][field] == cli["line_endings"][field], field
+    assert counts["line_endings"]["bom"] == "utf-8"
+    for field in ("records", "min_fields", "max_fields", "header_fields", "mismatched"):
+        assert counts["fields"][field] == cli["fields"][field], field
@@ -0,0 +1,59 @@
+use std::io::{BufReader, Read, Seek};
+
+use crate::decompress;
+
+const TAR_BLOCK: u64 = 512;
+const TAR_MAGIC_OFFSET: usize = 257;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// Possibly wrapped in any format `decompress` understands
+    Tar,
+    Zip,
+}
+
+fn is_zip(start: &[u8]) -> bool {
+    // local file header, or the end of central directory record of an empty zip
+    start.starts_with(b"PK\x03\x04") || start.starts_with(b"PK\x05\x06")
+}
+
+fn is_tar(header: &[u8]) -> bool {
+    // covers both POSIX "ustar\0" and old GNU "ustar  \0" headers
+    header
+        .get(TAR_MAGIC_OFFSET..)
+        .is_some_and(|magic| magic.starts_with(b"ustar"))
+}
+
+/// Recognizes zip archives and (optionally compressed) tar archives, leaving
+/// `input` rewound to its start either way.
+///
+/// Zip needs random access to its central directory, which is why the input
+/// has to be seekable; callers buffer non-seekable inputs like stdin first.
+pub fn detect<R: Read + Seek>(input: &mut R) -> std::io::Result<Option<Kind>> {
+    let mut start = Vec::new();
+    input.by_ref().take(4).read_to_end(&mut start)?;
+    input.rewind()?;
+    if is_zip(&start) {
+        return Ok(Some(Kind::Zip));
+    }
+
+    let mut header = Vec::new();
+    decompress::reader(BufReader::new(input.by_ref()))?
+        .take(TAR_BLOCK)
+        .read_to_end(&mut header)?;
+    input.rewind()?;
+    Ok(is_tar(&header).then_some(Kind::Tar))
+}
+
+#[test]
+fn test_detect() {
+    assert!(is_zip(b"PK\x03\x04rest"));
+    assert!(is_zip(b"PK\x05\x06"));
+    assert!(!is_zip(b"PK"));
+
+    let mut header = vec![0; TAR_BLOCK as usize];
+    assert!(!is_tar(&header));
+    header[TAR_MAGIC_OFFSET..TAR_MAGIC_OFFSET + 6].copy_from_slice(b"ustar\0");
+    assert!(is_tar(&header));
+    assert!(!is_tar(b"short"));
+}
@@ -0,0 +1,75 @@
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+use crate::{CountOptions, FileCounts, RecordCounter};
+
+// `read_record` for async readers.
+async fn read_record(
+    reader: &mut (impl AsyncBufRead + Unpin),
+    separator: &[u8],
+    buf: &mut Vec<u8>,
+) -> std::io::Result<usize> {
+    let last = *separator.last().expect("record separator is never empty");
+    let mut total = 0;
+    loop {
+        let bytes_read = reader.read_until(last, buf).await?;
+        total += bytes_read;
+        if bytes_read == 0 || buf.last() != Some(&last) || buf.ends_with(separator) {
+            return Ok(total);
+        }
+    }
+}
+
+/// `FileCounts::from_reader` for a tokio reader, with the same results and
+/// the same partial counts on a read error.
+pub async fn count_async(
+    mut reader: impl AsyncBufRead + Unpin,
+    options: &CountOptions,
+) -> (FileCounts, std::io::Result<()>) {
+    let mut counter = RecordCounter::new(options);
+    let mut buf = Vec::new();
+    let result = loop {
+        if counter.is_done() {
+            break Ok(());
+        }
+        match read_record(&mut reader, &options.record_separator, &mut buf).await {
+            Ok(0) => break Ok(()),
+            Ok(_) => {
+                counter.record(&buf);
+            }
+            Err(e) => break Err(e),
+        }
+        buf.clear();
+    };
+
+    (counter.finish(), result)
+}
+
+#[test]
+fn test_count_async() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let text = "The cat sat. It left!\r\n\r\nwide 世界\x00and\nno newline at end";
+    let options = CountOptions {
+        patterns: vec![regex::bytes::Regex::new("a").unwrap()],
+        match_mode: crate::MatchMode::Occurrences,
+        record_separator: b"\r\n".to_vec(),
+        line_endings: true,
+        prose: true,
+        ..CountOptions::default()
+    };
+
+    let (expected, result) = FileCounts::from_reader(text.as_bytes(), &options);
+    result.unwrap();
+    // a tiny buffer splits records and the separator across reads
+    let reader = tokio::io::BufReader::with_capacity(3, text.as_bytes());
+    let (counts, result) = runtime.block_on(count_async(reader, &options));
+    result.unwrap();
+
+    assert_eq!(
+        serde_json::to_string(&counts).unwrap(),
+        serde_json::to_string(&expected).unwrap()
+    );
+    assert_eq!(counts.lines, 3);
+    assert_eq!(counts.matches, [4]);
+}
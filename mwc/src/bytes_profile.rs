@@ -0,0 +1,219 @@
+use std::{fmt::Display, ops::AddAssign};
+
+use serde::{Deserialize, Serialize};
+
+/// Below this share of printable bytes, an input without NULs is binary.
+const TEXT_THRESHOLD: f64 = 0.95;
+
+/// What an input most likely is, judged by its bytes alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verdict {
+    Empty,
+    Text,
+    Binary,
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::Empty => write!(f, "empty"),
+            Verdict::Text => write!(f, "text"),
+            Verdict::Binary => write!(f, "binary"),
+        }
+    }
+}
+
+/// How often each byte value occurs, for triaging unknown inputs.
+///
+/// Bytes are printable when they belong to valid UTF-8 that isn't a control
+/// character, except for tabs, newlines, carriage returns and form feeds.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "Frequencies", into = "Summary")]
+pub struct ByteProfile {
+    // indexed by byte value; empty until an input has been scanned
+    frequencies: Vec<usize>,
+    printable: usize,
+}
+
+pub const COLUMN_COUNT: usize = 3;
+
+/// `--header` labels, in the order of [`ByteProfile::cells`].
+pub const LABELS: [&str; COLUMN_COUNT] = ["entropy", "printable", "verdict"];
+
+impl ByteProfile {
+    /// True unless --bytes-profile was in effect for these counts.
+    pub fn is_unscanned(&self) -> bool {
+        self.frequencies.is_empty()
+    }
+
+    pub fn scan(&mut self, record: &[u8]) {
+        if self.frequencies.is_empty() {
+            self.frequencies = vec![0; 256];
+        }
+        for &b in record {
+            self.frequencies[b as usize] += 1;
+        }
+        self.printable += record
+            .utf8_chunks()
+            .flat_map(|chunk| chunk.valid().chars())
+            .filter(|&c| !c.is_control() || matches!(c, '\t' | '\n' | '\r' | '\x0c'))
+            .map(char::len_utf8)
+            .sum::<usize>();
+    }
+
+    /// Occurrences of `byte`.
+    pub fn frequency(&self, byte: u8) -> usize {
+        self.frequencies.get(byte as usize).copied().unwrap_or(0)
+    }
+
+    fn total(&self) -> usize {
+        self.frequencies.iter().sum()
+    }
+
+    /// Shannon entropy in bits per byte, from 0 (one repeated byte) to 8
+    /// (every byte value equally often).
+    pub fn entropy(&self) -> f64 {
+        let total = self.total() as f64;
+        if total == 0.0 {
+            return 0.0;
+        }
+        self.frequencies
+            .iter()
+            .filter(|&&n| n > 0)
+            .map(|&n| {
+                let p = n as f64 / total;
+                p * (1.0 / p).log2()
+            })
+            .sum()
+    }
+
+    /// Share of printable bytes, between 0 and 1; 1 for empty inputs.
+    pub fn printable_ratio(&self) -> f64 {
+        match self.total() {
+            0 => 1.0,
+            total => self.printable as f64 / total as f64,
+        }
+    }
+
+    pub fn verdict(&self) -> Verdict {
+        if self.total() == 0 {
+            Verdict::Empty
+        } else if self.frequency(0) > 0 || self.printable_ratio() < TEXT_THRESHOLD {
+            Verdict::Binary
+        } else {
+            Verdict::Text
+        }
+    }
+
+    /// Byte values that occur, most frequent first.
+    pub fn table(&self) -> Vec<(u8, usize)> {
+        let mut table: Vec<(u8, usize)> = (0..=u8::MAX)
+            .map(|b| (b, self.frequency(b)))
+            .filter(|&(_, n)| n > 0)
+            .collect();
+        table.sort_by_key(|&(_, n)| std::cmp::Reverse(n));
+        table
+    }
+
+    pub fn cells(&self) -> [String; COLUMN_COUNT] {
+        if self.is_unscanned() {
+            return ["-", "-", "-"].map(String::from);
+        }
+        [
+            format!("{:.3}", self.entropy()),
+            format!("{:.1}%", self.printable_ratio() * 100.0),
+            self.verdict().to_string(),
+        ]
+    }
+}
+
+/// How `byte` reads in a frequency table: itself when it's printable ASCII,
+/// otherwise an escape such as `\n` or `\x1b`.
+pub fn describe(byte: u8) -> String {
+    match byte {
+        b'\t' => "\\t".into(),
+        b'\n' => "\\n".into(),
+        b'\r' => "\\r".into(),
+        0 => "\\0".into(),
+        b' ' => "' '".into(),
+        b if b.is_ascii_graphic() => (b as char).to_string(),
+        b => format!("\\x{:02x}", b),
+    }
+}
+
+impl AddAssign<&Self> for ByteProfile {
+    fn add_assign(&mut self, rhs: &Self) {
+        if self.frequencies.len() < rhs.frequencies.len() {
+            self.frequencies.resize(rhs.frequencies.len(), 0);
+        }
+        for (count, rhs_count) in self.frequencies.iter_mut().zip(&rhs.frequencies) {
+            *count += rhs_count;
+        }
+        self.printable += rhs.printable;
+    }
+}
+
+// The JSON form spells out what the frequencies imply
+#[derive(Serialize)]
+struct Summary {
+    entropy: f64,
+    printable_ratio: f64,
+    verdict: Verdict,
+    printable: usize,
+    frequencies: Vec<usize>,
+}
+
+impl From<ByteProfile> for Summary {
+    fn from(profile: ByteProfile) -> Self {
+        Self {
+            entropy: profile.entropy(),
+            printable_ratio: profile.printable_ratio(),
+            verdict: profile.verdict(),
+            printable: profile.printable,
+            frequencies: profile.frequencies,
+        }
+    }
+}
+
+// ...and reading it back only needs what they're derived from
+#[derive(Deserialize)]
+struct Frequencies {
+    printable: usize,
+    frequencies: Vec<usize>,
+}
+
+impl From<Frequencies> for ByteProfile {
+    fn from(value: Frequencies) -> Self {
+        Self {
+            frequencies: value.frequencies,
+            printable: value.printable,
+        }
+    }
+}
+
+#[test]
+fn test_scan_bytes_profile() {
+    let mut profile = ByteProfile::default();
+    assert!(profile.is_unscanned());
+    for record in [&b"abab\n"[..], b"\xc3\xa9\x01"] {
+        profile.scan(record);
+    }
+
+    assert_eq!(profile.frequency(b'a'), 2);
+    assert_eq!(profile.frequency(b'\n'), 1);
+    assert_eq!(profile.table()[0], (b'a', 2));
+    // everything but the \x01 control byte
+    assert_eq!(profile.printable, 7);
+    assert_eq!(profile.verdict(), Verdict::Binary);
+
+    let mut uniform = ByteProfile::default();
+    uniform.scan(&(0..=u8::MAX).collect::<Vec<_>>());
+    assert_eq!(uniform.entropy(), 8.0);
+
+    let mut repeated = ByteProfile::default();
+    repeated.scan(b"zzzz");
+    assert_eq!(repeated.entropy(), 0.0);
+    assert_eq!(repeated.verdict(), Verdict::Text);
+    assert_eq!(repeated.cells(), ["0.000", "100.0%", "text"]);
+}
@@ -0,0 +1,214 @@
+use std::{
+    collections::HashMap,
+    fs::{File, Metadata, OpenOptions},
+    io::{BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::FileCounts;
+
+const FORMAT_VERSION: u32 = 2;
+
+// A change within the same timestamp tick as the one we counted would go
+// unnoticed, so files modified this recently are counted but never cached.
+const RACY_WINDOW: Duration = Duration::from_secs(1);
+
+/// Identifies a file independent of its path.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+struct Key {
+    dev: u64,
+    ino: u64,
+}
+
+/// What the file looked like when it was counted; any difference is a miss.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Stamp {
+    size: u64,
+    mtime_sec: i64,
+    mtime_nsec: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    #[serde(flatten)]
+    key: Key,
+    #[serde(flatten)]
+    stamp: Stamp,
+    counts: FileCounts,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheFile {
+    version: u32,
+    /// Entries by the fingerprint of the config they were counted with
+    configs: HashMap<String, Vec<Entry>>,
+}
+
+#[derive(Default, Debug, Clone, Copy)]
+pub struct Stats {
+    pub hits: usize,
+    pub misses: usize,
+    pub stored: usize,
+}
+
+/// Counts of unchanged files from previous runs, stored at `--cache=PATH`.
+///
+/// Writers hold an exclusive lock on `PATH.lock` while they merge their new
+/// entries into whatever is on disk and atomically rename the result into
+/// place, so concurrent mwc processes sharing a cache never lose each other's
+/// work. Readers only need a shared lock.
+pub struct Cache {
+    path: PathBuf,
+    config: String,
+    started: SystemTime,
+    entries: HashMap<Key, (Stamp, FileCounts)>,
+    new_entries: Vec<Entry>,
+    pub stats: Stats,
+}
+
+impl Cache {
+    /// `config` fingerprints every setting that changes what gets counted.
+    pub fn open(path: &Path, config: String) -> std::io::Result<Self> {
+        let lock = lock_file(path)?;
+        lock.lock_shared()?;
+        let entries = read_cache(path)?
+            .configs
+            .remove(&config)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|entry| (entry.key, (entry.stamp, entry.counts)))
+            .collect();
+        lock.unlock()?;
+
+        Ok(Self {
+            path: path.into(),
+            config,
+            started: SystemTime::now(),
+            entries,
+            new_entries: Vec::new(),
+            stats: Stats::default(),
+        })
+    }
+
+    pub fn lookup(&mut self, metadata: &Metadata) -> Option<FileCounts> {
+        let found = file_id(metadata).and_then(|(key, stamp)| {
+            self.entries
+                .get(&key)
+                .filter(|(cached, _)| *cached == stamp)
+                .map(|(_, counts)| counts.clone())
+        });
+        match found {
+            Some(_) => self.stats.hits += 1,
+            None => self.stats.misses += 1,
+        }
+        found
+    }
+
+    pub fn store(&mut self, metadata: &Metadata, counts: &FileCounts) {
+        let racy = metadata
+            .modified()
+            .map_or(true, |mtime| mtime + RACY_WINDOW > self.started);
+        if racy {
+            return;
+        }
+        if let Some((key, stamp)) = file_id(metadata) {
+            self.new_entries.push(Entry {
+                key,
+                stamp,
+                counts: counts.clone(),
+            });
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Merge the entries counted by this run into the cache on disk.
+    pub fn save(&mut self) -> std::io::Result<()> {
+        if self.new_entries.is_empty() {
+            return Ok(());
+        }
+
+        let lock = lock_file(&self.path)?;
+        lock.lock()?;
+        let mut cache = read_cache(&self.path)?;
+        let entries = cache.configs.entry(self.config.clone()).or_default();
+        let mut merged: HashMap<Key, Entry> = entries
+            .drain(..)
+            .map(|entry| (entry.key.clone(), entry))
+            .collect();
+        self.stats.stored = self.new_entries.len();
+        for entry in self.new_entries.drain(..) {
+            merged.insert(entry.key.clone(), entry);
+        }
+        entries.extend(merged.into_values());
+
+        let tmp_path = sibling(&self.path, "tmp");
+        let mut writer = BufWriter::new(File::create(&tmp_path)?);
+        serde_json::to_writer(&mut writer, &cache)?;
+        writer.flush()?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        lock.unlock()
+    }
+}
+
+fn sibling(path: &Path, extension: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(extension);
+    name.into()
+}
+
+fn lock_file(path: &Path) -> std::io::Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(sibling(path, "lock"))
+}
+
+// A cache from another format version, or one that doesn't parse, is
+// treated as empty and replaced on save.
+fn read_cache(path: &Path) -> std::io::Result<CacheFile> {
+    let empty = CacheFile {
+        version: FORMAT_VERSION,
+        configs: HashMap::new(),
+    };
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(empty),
+        Err(e) => return Err(e),
+    };
+    match serde_json::from_reader::<_, CacheFile>(BufReader::new(file)) {
+        Ok(cache) if cache.version == FORMAT_VERSION => Ok(cache),
+        Err(e) if e.is_io() => Err(e.into()),
+        _ => Ok(empty),
+    }
+}
+
+#[cfg(unix)]
+fn file_id(metadata: &Metadata) -> Option<(Key, Stamp)> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some((
+        Key {
+            dev: metadata.dev(),
+            ino: metadata.ino(),
+        },
+        Stamp {
+            size: metadata.size(),
+            mtime_sec: metadata.mtime(),
+            mtime_nsec: metadata.mtime_nsec(),
+        },
+    ))
+}
+
+// Without inode numbers there's no reliable identity, so nothing is cached.
+#[cfg(not(unix))]
+fn file_id(_metadata: &Metadata) -> Option<(Key, Stamp)> {
+    None
+}
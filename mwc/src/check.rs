@@ -0,0 +1,118 @@
+use std::{collections::HashMap, fmt::Write};
+
+use crate::{Column, FileCounts, report::Report};
+
+/// Per-file ceilings from `--max-lines`, `--max-width` and `--max-bytes`.
+#[derive(Default)]
+pub struct Limits {
+    pub max_lines: Option<usize>,
+    pub max_line_length: Option<usize>,
+    pub max_bytes: Option<usize>,
+}
+
+impl Limits {
+    /// One message per limit that `counts` goes over.
+    pub fn violations(&self, counts: &FileCounts) -> Vec<String> {
+        [
+            (Column::Lines, "--max-lines", self.max_lines),
+            (Column::MaxLineLength, "--max-width", self.max_line_length),
+            (Column::Bytes, "--max-bytes", self.max_bytes),
+        ]
+        .into_iter()
+        .filter_map(|(column, flag, limit)| {
+            let limit = limit?;
+            let count = column.count(counts);
+            (count > limit)
+                .then(|| format!("{} {} exceeds {} {}", count, column.name(), flag, limit))
+        })
+        .collect()
+    }
+}
+
+/// Result of diffing a run against a `--compare` baseline.
+pub struct Comparison {
+    pub lines: Vec<String>,
+    pub exceeded: bool,
+}
+
+/// Describe how each of `columns` changed for every input, flagging growth
+/// beyond `max_growth` percent. Inputs only present on one side are listed
+/// as new or removed but never fail the comparison, since there's nothing to
+/// measure growth against.
+pub fn compare<'a>(
+    baseline: &Report,
+    current: impl IntoIterator<Item = (String, &'a FileCounts)>,
+    total: &FileCounts,
+    columns: &[Column],
+    max_growth: Option<f64>,
+) -> Comparison {
+    let mut previous: HashMap<&str, &FileCounts> = baseline
+        .files
+        .iter()
+        .map(|entry| (entry.name.as_str(), &entry.counts))
+        .collect();
+    let mut comparison = Comparison {
+        lines: Vec::new(),
+        exceeded: false,
+    };
+
+    for (name, counts) in current {
+        match previous.remove(name.as_str()) {
+            Some(before) => {
+                let (line, exceeded) = describe(&name, before, counts, columns, max_growth);
+                comparison.lines.push(line);
+                comparison.exceeded |= exceeded;
+            }
+            None => comparison.lines.push(format!("{}: new", name)),
+        }
+    }
+    let mut removed: Vec<_> = previous.into_keys().collect();
+    removed.sort_unstable();
+    for name in removed {
+        comparison.lines.push(format!("{}: removed", name));
+    }
+
+    let (line, exceeded) = describe("total", &baseline.total, total, columns, max_growth);
+    comparison.lines.push(line);
+    comparison.exceeded |= exceeded;
+
+    comparison
+}
+
+fn describe(
+    name: &str,
+    before: &FileCounts,
+    after: &FileCounts,
+    columns: &[Column],
+    max_growth: Option<f64>,
+) -> (String, bool) {
+    let mut line = format!("{}:", name);
+    let mut exceeded = false;
+    for (i, &column) in columns.iter().enumerate() {
+        let (old, new) = (column.count(before), column.count(after));
+        let delta = new as i128 - old as i128;
+        let separator = if i == 0 { " " } else { ", " };
+        let _ = write!(
+            line,
+            "{}{} {} -> {} ({:+}, ",
+            separator,
+            column.name(),
+            old,
+            new,
+            delta
+        );
+        if old == 0 {
+            line.push_str("n/a)");
+            continue;
+        }
+
+        let percent = delta as f64 * 100.0 / old as f64;
+        let _ = write!(line, "{:+.1}%)", percent);
+        if max_growth.is_some_and(|max| percent > max) {
+            line.push_str(" exceeds --max-growth");
+            exceeded = true;
+        }
+    }
+
+    (line, exceeded)
+}
@@ -0,0 +1,135 @@
+use std::{
+    cell::Cell,
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use mwc::Progress;
+use serde::{Deserialize, Serialize};
+
+use crate::{CountsName, FileCounts};
+
+const FORMAT_VERSION: u32 = 1;
+
+/// What `--checkpoint` saves for `--resume`.
+#[derive(Serialize, Deserialize)]
+struct Checkpoint {
+    version: u32,
+    // the counting options and inputs this was written for; resuming with
+    // anything else would mix up counts
+    settings: String,
+    inputs: Vec<PathBuf>,
+    #[serde(flatten)]
+    state: State,
+}
+
+/// Everything counted so far.
+#[derive(Serialize, Deserialize)]
+pub struct State {
+    /// Results of the inputs before `next_input`, in output order
+    pub rows: Vec<Row>,
+    pub totals: FileCounts,
+    pub counted: usize,
+    pub stdin_seen: bool,
+    pub next_input: usize,
+    /// How far into `next_input` counting got, if it's a seekable file
+    pub current: Option<Partial>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Row {
+    Counted {
+        name: CountsName,
+        counts: Box<FileCounts>,
+    },
+    // errors are only ever shown, so their message is all that's kept
+    Failed {
+        message: String,
+    },
+}
+
+/// A file counted up to `offset`, where it carries on from `progress`.
+#[derive(Serialize, Deserialize)]
+pub struct Partial {
+    pub offset: u64,
+    pub progress: Progress,
+}
+
+/// Loads the state saved at `path`, as long as it was written for the same
+/// `settings` and `inputs`.
+pub fn load(path: &Path, settings: &str, inputs: &[PathBuf]) -> std::io::Result<State> {
+    let file = File::open(path)?;
+    let checkpoint: Checkpoint = serde_json::from_reader(BufReader::new(file))?;
+    if checkpoint.version != FORMAT_VERSION {
+        return Err(std::io::Error::other(
+            "written by an incompatible version of mwc",
+        ));
+    }
+    if checkpoint.settings != settings || checkpoint.inputs != inputs {
+        return Err(std::io::Error::other(
+            "written for other inputs or counting options",
+        ));
+    }
+    Ok(checkpoint.state)
+}
+
+/// Saves progress to `--checkpoint=PATH`, at most once per `interval`.
+///
+/// Each save replaces the previous one atomically, so a preempted job always
+/// leaves a whole checkpoint behind.
+pub struct Writer {
+    path: PathBuf,
+    interval: Duration,
+    settings: String,
+    inputs: Vec<PathBuf>,
+    last_saved: Cell<Instant>,
+}
+
+impl Writer {
+    pub fn new(path: PathBuf, interval: Duration, settings: String, inputs: Vec<PathBuf>) -> Self {
+        Self {
+            path,
+            interval,
+            settings,
+            inputs,
+            last_saved: Cell::new(Instant::now()),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn due(&self) -> bool {
+        self.last_saved.get().elapsed() >= self.interval
+    }
+
+    pub fn save(&self, state: State) -> std::io::Result<()> {
+        let checkpoint = Checkpoint {
+            version: FORMAT_VERSION,
+            settings: self.settings.clone(),
+            inputs: self.inputs.clone(),
+            state,
+        };
+        let mut tmp_name = self.path.as_os_str().to_owned();
+        tmp_name.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_name);
+        let mut writer = BufWriter::new(File::create(&tmp_path)?);
+        serde_json::to_writer(&mut writer, &checkpoint)?;
+        writer.into_inner()?.sync_all()?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        self.last_saved.set(Instant::now());
+        Ok(())
+    }
+
+    /// Once the output is out, there's nothing left to resume.
+    pub fn remove(&self) -> std::io::Result<()> {
+        match std::fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
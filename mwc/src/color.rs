@@ -0,0 +1,53 @@
+use std::io::IsTerminal;
+
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Color when writing to a terminal and NO_COLOR isn't set
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn enabled_for(self, stream: &impl IsTerminal) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            // https://no-color.org: any non-empty value disables color
+            ColorChoice::Auto => {
+                stream.is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// Input names
+    Dim,
+    /// The total row
+    Bold,
+    /// Errors and limit violations
+    Red,
+}
+
+impl Style {
+    fn code(self) -> &'static str {
+        match self {
+            Style::Dim => "2",
+            Style::Bold => "1",
+            Style::Red => "31",
+        }
+    }
+}
+
+/// Wraps `text` in the escape codes for `style` if `enabled`.
+pub fn paint(text: &str, style: Style, enabled: bool) -> String {
+    if enabled && !text.is_empty() {
+        format!("\x1b[{}m{}\x1b[0m", style.code(), text)
+    } else {
+        text.to_string()
+    }
+}
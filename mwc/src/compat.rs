@@ -0,0 +1,82 @@
+use std::fs::Metadata;
+
+use crate::Input;
+
+/// Whose conventions the text output follows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Conformance {
+    /// Every column as wide as its widest count
+    Mwc,
+    /// POSIX's `"%d %d %d %s\n"`: single spaces and no padding
+    Posix,
+    /// GNU wc's: one width for every column, see [`gnu_width`]
+    Gnu,
+}
+
+impl Conformance {
+    /// `--posix` or `--gnu` if given, otherwise POSIX if `POSIXLY_CORRECT` is set.
+    pub fn from_flags(posix: bool, gnu: bool) -> Self {
+        if posix {
+            Conformance::Posix
+        } else if gnu {
+            Conformance::Gnu
+        } else if std::env::var_os("POSIXLY_CORRECT").is_some() {
+            Conformance::Posix
+        } else {
+            Conformance::Mwc
+        }
+    }
+}
+
+/// The width GNU wc pads every count to, following its `compute_number_width`.
+///
+/// It's the number of digits in the combined size of the regular files among
+/// `inputs`, but at least 7 if any input is something else, like a pipe on
+/// stdin; that's the wide-columns-with-stdin quirk in LEARNINGS.md. A single
+/// input with a single column isn't padded at all.
+pub fn gnu_width(inputs: &[Input], columns: usize) -> usize {
+    // no inputs means stdin
+    let stdin = [Input::StdIn];
+    let inputs = if inputs.is_empty() {
+        &stdin[..]
+    } else {
+        inputs
+    };
+    if inputs.len() == 1 && columns == 1 {
+        return 1;
+    }
+
+    let mut minimum = 1;
+    let mut regular_total = 0;
+    // like GNU, inputs that can't be examined don't count either way
+    for size in inputs.iter().filter_map(regular_size) {
+        match size {
+            Some(size) => regular_total += size,
+            None => minimum = 7,
+        }
+    }
+    crate::digits(regular_total as usize).max(minimum)
+}
+
+// The size of a regular file, None for anything else, or nothing at all if
+// the input can't be examined.
+fn regular_size(input: &Input) -> Option<Option<u64>> {
+    let metadata = match input {
+        Input::File(path) => std::fs::metadata(path).ok()?,
+        Input::StdIn => stdin_metadata()?,
+    };
+    Some(metadata.is_file().then_some(metadata.len()))
+}
+
+#[cfg(unix)]
+fn stdin_metadata() -> Option<Metadata> {
+    use std::os::fd::AsFd;
+
+    let stdin = std::io::stdin().as_fd().try_clone_to_owned().ok()?;
+    std::fs::File::from(stdin).metadata().ok()
+}
+
+#[cfg(not(unix))]
+fn stdin_metadata() -> Option<Metadata> {
+    None
+}
@@ -0,0 +1,102 @@
+use std::{
+    cell::Cell,
+    io::{BufRead, BufReader, Read},
+    rc::Rc,
+};
+
+/// Compression formats recognized by their magic bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Xz,
+    Bzip2,
+}
+
+impl Compression {
+    pub fn detect(start: &[u8]) -> Option<Self> {
+        if start.starts_with(&[0x1F, 0x8B]) {
+            Some(Compression::Gzip)
+        } else if start.starts_with(&[0x28, 0xB5, 0x2F, 0xFD]) {
+            Some(Compression::Zstd)
+        } else if start.starts_with(&[0xFD, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Compression::Xz)
+        } else if start.starts_with(b"BZh") {
+            Some(Compression::Bzip2)
+        } else {
+            None
+        }
+    }
+}
+
+/// Wraps `reader` in a decoder when it starts with a known magic number,
+/// otherwise hands the bytes through untouched (like `zcat -f`).
+///
+/// All the decoders handle concatenated streams, since that's what you get
+/// from `cat a.gz b.gz` or from rotated logs that were appended to.
+pub fn reader<'a>(mut reader: impl BufRead + 'a) -> std::io::Result<Box<dyn BufRead + 'a>> {
+    let compression = Compression::detect(reader.fill_buf()?);
+    Ok(match compression {
+        Some(Compression::Gzip) => {
+            Box::new(BufReader::new(flate2::bufread::MultiGzDecoder::new(reader)))
+        }
+        Some(Compression::Zstd) => Box::new(BufReader::new(
+            zstd::stream::read::Decoder::with_buffer(reader)?,
+        )),
+        Some(Compression::Xz) => Box::new(BufReader::new(
+            xz2::bufread::XzDecoder::new_multi_decoder(reader),
+        )),
+        Some(Compression::Bzip2) => {
+            Box::new(BufReader::new(bzip2::bufread::MultiBzDecoder::new(reader)))
+        }
+        None => Box::new(reader),
+    })
+}
+
+/// Counts the bytes pulled through it, so the on-disk size of a compressed
+/// input can be reported next to the counts of its decompressed content.
+pub struct RawBytes<R> {
+    inner: R,
+    count: Rc<Cell<usize>>,
+}
+
+impl<R> RawBytes<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            count: Rc::default(),
+        }
+    }
+
+    /// Handle that keeps reporting the count after `self` has been consumed.
+    pub fn counter(&self) -> Rc<Cell<usize>> {
+        Rc::clone(&self.count)
+    }
+}
+
+impl<R: Read> Read for RawBytes<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let bytes_read = self.inner.read(buf)?;
+        self.count.set(self.count.get() + bytes_read);
+        Ok(bytes_read)
+    }
+}
+
+#[test]
+fn test_detect() {
+    assert_eq!(
+        Compression::detect(&[0x1F, 0x8B, 0x08]),
+        Some(Compression::Gzip)
+    );
+    assert_eq!(
+        Compression::detect(&[0x28, 0xB5, 0x2F, 0xFD, 0x00]),
+        Some(Compression::Zstd)
+    );
+    assert_eq!(
+        Compression::detect(b"\xFD7zXZ\x00\x00"),
+        Some(Compression::Xz)
+    );
+    assert_eq!(Compression::detect(b"BZh91AY"), Some(Compression::Bzip2));
+    assert_eq!(Compression::detect(b"hello"), None);
+    assert_eq!(Compression::detect(b""), None);
+}
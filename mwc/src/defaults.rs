@@ -0,0 +1,383 @@
+use std::{
+    collections::HashSet,
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+use clap::{Arg, ArgAction, ArgMatches, Command, Id, error::ErrorKind, parser::ValueSource};
+
+/// Options that come before those on the command line, split at whitespace.
+pub const ENV_VAR: &str = "MWC_OPTIONS";
+/// Looked for in the working directory and its ancestors.
+const PROJECT_FILE: &str = ".mwc.toml";
+// these only make sense on the command line
+const NOT_SETTABLE: [&str; 4] = ["help", "version", "print-config", "no-config"];
+// together these pick the columns, so giving any of them replaces the rest
+const COLUMN_SELECTION: [&str; 6] = [
+    "lines",
+    "words",
+    "bytes",
+    "chars",
+    "max_line_length",
+    "columns",
+];
+// what `CountsConfig::settings` gives as columns, patterns, word_regex,
+// record_separator and fields instead
+const SPELLED_OTHERWISE: [&str; 11] = [
+    "lines",
+    "words",
+    "bytes",
+    "chars",
+    "max_line_length",
+    "paragraphs",
+    "sentences",
+    "fixed_strings",
+    "word_chars",
+    "null_data",
+    "rfc4180",
+];
+
+/// Defaults for `Opts` from one place.
+pub struct Layer {
+    /// A config file's path, or `MWC_OPTIONS`
+    pub origin: String,
+    settings: Vec<Setting>,
+}
+
+// One option, as the arguments that give it
+struct Setting {
+    id: Id,
+    args: Vec<OsString>,
+}
+
+/// Loads every source of defaults, lowest precedence first: the user's
+/// `mpsx/mwc.toml` under `$XDG_CONFIG_HOME` (or `~/.config`), the nearest
+/// `.mwc.toml` and `MWC_OPTIONS`.
+///
+/// Config files map long option names, with `-` or `_`, to their values:
+/// `true` for flags, strings and numbers for values and arrays for
+/// repeatable options.
+pub fn load(command: &Command) -> Result<Vec<Layer>, String> {
+    let mut layers = Vec::new();
+    for path in [user_file(), project_file()].into_iter().flatten() {
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(format!("{}: {}", path.display(), e)),
+        };
+        let origin = path.display().to_string();
+        let settings = from_toml(command, &text).map_err(|e| format!("{}: {}", origin, e))?;
+        layers.push(Layer { origin, settings });
+    }
+
+    if let Some(value) = std::env::var_os(ENV_VAR) {
+        let value = value
+            .into_string()
+            .map_err(|_| format!("{}: not valid UTF-8", ENV_VAR))?;
+        let args = value.split_whitespace().map(OsString::from).collect();
+        let settings = from_args(command, args).map_err(|e| format!("{}: {}", ENV_VAR, e))?;
+        layers.push(Layer {
+            origin: ENV_VAR.into(),
+            settings,
+        });
+    }
+    Ok(layers)
+}
+
+fn user_file() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(config_home.join("mpsx").join("mwc.toml"))
+}
+
+fn project_file() -> Option<PathBuf> {
+    let dir = std::env::current_dir().ok()?;
+    dir.ancestors()
+        .map(|dir| dir.join(PROJECT_FILE))
+        .find(|path| path.is_file())
+}
+
+/// `cli` with the defaults of `layers` put in front of its arguments, except
+/// for options that are given at a higher level, or that conflict with ones
+/// that are (a default `--format` doesn't stop a `--compare`). The column
+/// selection counts as one option: a `-w` leaves out a default `lines`.
+pub fn apply(command: &Command, cli: Vec<OsString>, layers: &[Layer]) -> Vec<OsString> {
+    let Some((program, args)) = cli.split_first() else {
+        return cli;
+    };
+    let Ok(matches) = command
+        .clone()
+        .ignore_errors(true)
+        .try_get_matches_from(&cli)
+    else {
+        return cli;
+    };
+    // options on the command line, then also the defaults taken so far
+    let mut present: Vec<&Arg> = given_args(command, &matches).collect();
+    let mut given: HashSet<Id> = present.iter().map(|arg| arg.get_id().clone()).collect();
+    select_columns(&mut given);
+
+    let mut defaults: Vec<OsString> = Vec::new();
+    for layer in layers.iter().rev() {
+        let mut set = Vec::new();
+        for setting in &layer.settings {
+            if given.contains(&setting.id) {
+                continue;
+            }
+            let Some(arg) = command
+                .get_arguments()
+                .find(|arg| arg.get_id() == &setting.id)
+            else {
+                continue;
+            };
+            if !present.iter().any(|other| conflicting(command, arg, other)) {
+                defaults.extend(setting.args.iter().cloned());
+                set.push(setting.id.clone());
+                present.push(arg);
+            }
+        }
+        given.extend(set);
+        select_columns(&mut given);
+    }
+
+    std::iter::once(program)
+        .chain(&defaults)
+        .chain(args)
+        .cloned()
+        .collect()
+}
+
+fn conflicting(command: &Command, a: &Arg, b: &Arg) -> bool {
+    let conflicts_with = |a: &Arg, b: &Arg| {
+        command
+            .get_arg_conflicts_with(a)
+            .iter()
+            .any(|arg| arg.get_id() == b.get_id())
+    };
+    conflicts_with(a, b) || conflicts_with(b, a)
+}
+
+/// Adds the options a config file can set that `settings` leaves out, as
+/// `matches` has them: flags on or off, and the values that were given.
+pub fn complete(command: &Command, matches: &ArgMatches, settings: &mut toml::Table) {
+    for arg in command.get_arguments() {
+        let id = arg.get_id().as_str();
+        let Some(long) = arg.get_long() else {
+            continue;
+        };
+        let key = long.replace('-', "_");
+        if arg.is_positional()
+            || NOT_SETTABLE.contains(&long)
+            || SPELLED_OTHERWISE.contains(&id)
+            || settings.contains_key(&key)
+        {
+            continue;
+        }
+        let value = if matches!(arg.get_action(), ArgAction::SetTrue) {
+            matches.get_flag(id).into()
+        } else if matches.value_source(id) == Some(ValueSource::CommandLine) {
+            let mut values: Vec<toml::Value> = matches
+                .get_raw(id)
+                .into_iter()
+                .flatten()
+                .map(|value| value.to_string_lossy().into_owned().into())
+                .collect();
+            match values.len() {
+                _ if matches!(arg.get_action(), ArgAction::Append) => values.into(),
+                // an optional value left out
+                0 => true.into(),
+                _ => values.remove(0),
+            }
+        } else {
+            continue;
+        };
+        settings.insert(key, value);
+    }
+}
+
+fn select_columns(given: &mut HashSet<Id>) {
+    if COLUMN_SELECTION.iter().any(|id| given.contains(*id)) {
+        given.extend(COLUMN_SELECTION.map(Id::from));
+    }
+}
+
+fn from_toml(command: &Command, text: &str) -> Result<Vec<Setting>, String> {
+    let table: toml::Table = text.parse().map_err(|e: toml::de::Error| {
+        // the full message spans several lines, quoting the offending one
+        e.message().to_string()
+    })?;
+    table
+        .iter()
+        .map(|(key, value)| {
+            let arg = settable(command, &key.replace('_', "-"))
+                .ok_or_else(|| format!("unknown option '{}'", key))?;
+            let long = format!("--{}", arg.get_long().unwrap_or_default());
+            let values = match value {
+                toml::Value::Array(values) => values.iter().collect(),
+                value => vec![value],
+            };
+
+            let mut args = Vec::new();
+            for value in values {
+                match value {
+                    // `false` leaves a flag off, overriding a lower level
+                    toml::Value::Boolean(set) if takes_no_value(arg) => {
+                        if *set {
+                            args.push(long.clone().into());
+                        }
+                    }
+                    toml::Value::String(s) => args.push(format!("{}={}", long, s).into()),
+                    toml::Value::Integer(_) | toml::Value::Float(_) | toml::Value::Boolean(_) => {
+                        args.push(format!("{}={}", long, value).into())
+                    }
+                    _ => {
+                        return Err(format!(
+                            "'{}' must be a boolean, number, string or an array of those",
+                            key
+                        ));
+                    }
+                }
+            }
+            let setting = Setting {
+                id: arg.get_id().clone(),
+                args,
+            };
+            check(command, &setting).map_err(|e| format!("{}: {}", key, e))?;
+            Ok(setting)
+        })
+        .collect()
+}
+
+fn from_args(command: &Command, args: Vec<OsString>) -> Result<Vec<Setting>, String> {
+    let argv = std::iter::once(OsString::from("mwc")).chain(args);
+    let matches = command
+        .clone()
+        .ignore_errors(true)
+        .try_get_matches_from(argv.clone())
+        .map_err(message)?;
+    // --cache-stats without --cache is fine here, as long as it's given later
+    if let Err(e) = command.clone().try_get_matches_from(argv)
+        && e.kind() != ErrorKind::MissingRequiredArgument
+    {
+        return Err(message(e));
+    }
+
+    given_args(command, &matches)
+        .map(|arg| {
+            let long = match arg.get_long() {
+                Some(long) if !arg.is_positional() && !NOT_SETTABLE.contains(&long) => long,
+                Some(long) => return Err(format!("--{} can't be given here", long)),
+                None => return Err("inputs can't be given here".into()),
+            };
+            let raw: Vec<_> = matches
+                .get_raw(arg.get_id().as_str())
+                .into_iter()
+                .flatten()
+                .collect();
+            let args = if matches!(arg.get_action(), ArgAction::SetTrue) || raw.is_empty() {
+                vec![format!("--{}", long).into()]
+            } else {
+                raw.into_iter()
+                    .map(|value| {
+                        let mut arg = OsString::from(format!("--{}=", long));
+                        arg.push(value);
+                        arg
+                    })
+                    .collect()
+            };
+            Ok(Setting {
+                id: arg.get_id().clone(),
+                args,
+            })
+        })
+        .collect()
+}
+
+// Arguments given explicitly, rather than taking their default
+fn given_args<'a>(command: &'a Command, matches: &'a ArgMatches) -> impl Iterator<Item = &'a Arg> {
+    command
+        .get_arguments()
+        .filter(|arg| matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine))
+}
+
+fn settable<'a>(command: &'a Command, long: &str) -> Option<&'a Arg> {
+    if NOT_SETTABLE.contains(&long) {
+        return None;
+    }
+    command
+        .get_arguments()
+        .find(|arg| !arg.is_positional() && arg.get_long() == Some(long))
+}
+
+fn takes_no_value(arg: &Arg) -> bool {
+    matches!(arg.get_action(), ArgAction::SetTrue)
+        || arg.get_num_args().is_some_and(|n| n.min_values() == 0)
+}
+
+// Catches bad values where they're written, rather than as though they'd
+// been given on the command line
+fn check(command: &Command, setting: &Setting) -> Result<(), String> {
+    let argv = std::iter::once(OsString::from("mwc")).chain(setting.args.iter().cloned());
+    match command.clone().try_get_matches_from(argv) {
+        Err(e) if e.kind() != ErrorKind::MissingRequiredArgument => Err(message(e)),
+        _ => Ok(()),
+    }
+}
+
+// The gist of a clap error, without its usage and help hints
+fn message(e: clap::Error) -> String {
+    let rendered = e.render().to_string();
+    let first = rendered.lines().next().unwrap_or_default();
+    first.trim_start_matches("error: ").to_string()
+}
+
+#[test]
+fn test_apply_layers() {
+    use clap::CommandFactory;
+
+    let mut command = crate::Opts::command();
+    command.build();
+    let layer = |settings| Layer {
+        origin: String::new(),
+        settings,
+    };
+    let user = from_toml(
+        &command,
+        "format = \"json\"\nlines = true\nwhere = [\"width>80\"]",
+    )
+    .unwrap();
+    let env = from_args(&command, vec!["-lw".into(), "--color=never".into()]).unwrap();
+    let cli = [
+        "mwc",
+        "--compare",
+        "base.json",
+        "--color",
+        "always",
+        "a.txt",
+    ];
+    let args = apply(
+        &command,
+        cli.map(OsString::from).to_vec(),
+        &[layer(user), layer(env)],
+    );
+
+    // --format conflicts with --compare; --where is kept, needing --per-line
+    let expected = [
+        "mwc",
+        "--lines",
+        "--words",
+        "--where=width>80",
+        "--compare",
+        "base.json",
+        "--color",
+        "always",
+        "a.txt",
+    ];
+    assert_eq!(args, expected.map(OsString::from));
+
+    assert!(from_toml(&command, "help = true").is_err());
+    assert!(from_toml(&command, "max-records = \"many\"").is_err());
+    assert!(from_args(&command, vec!["a.txt".into()]).is_err());
+}
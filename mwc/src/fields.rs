@@ -0,0 +1,188 @@
+use std::ops::AddAssign;
+
+use serde::{Deserialize, Serialize};
+
+const QUOTE: u8 = b'"';
+
+/// How `--fields` splits records into fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Delimited {
+    pub delimiter: u8,
+    /// RFC 4180: a field that starts with `"` runs to the next lone `"`,
+    /// taking delimiters and record separators with it; `""` is a quote
+    pub quoting: bool,
+}
+
+/// How many fields the records of delimited data have.
+///
+/// Records are fed in order via [`Fields::scan`]. With quoting, a record can
+/// span several of them, so the counts only cover records that have ended.
+/// Blank records are skipped, and the first record is taken to be the header.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct Fields {
+    pub records: usize,
+    // over all records, 0 without any
+    pub min_fields: usize,
+    pub max_fields: usize,
+    /// Fields in the header; `None` for totals over inputs that disagree
+    pub header_fields: Option<usize>,
+    /// Records whose field count differs from the header's
+    pub mismatched: usize,
+    // present in JSON only once scanned, so deserializing means scanned
+    #[serde(skip, default = "scanned")]
+    scanned: bool,
+    // state of a record that hasn't ended yet
+    #[serde(default, skip_serializing_if = "is_zero")]
+    delimiters: usize,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    in_quotes: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    in_field: bool,
+}
+
+fn scanned() -> bool {
+    true
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+pub const COLUMN_COUNT: usize = 3;
+
+/// `--header` labels, in the order of [`Fields::cells`].
+pub const LABELS: [&str; COLUMN_COUNT] = ["min_fields", "max_fields", "mismatched"];
+
+impl Fields {
+    /// True unless --fields was in effect for these counts.
+    pub fn is_unscanned(&self) -> bool {
+        !self.scanned
+    }
+
+    /// `record` includes its `separator`, unless it's the unterminated last one.
+    pub fn scan(&mut self, record: &[u8], separator: &[u8], delimited: &Delimited) {
+        self.scanned = true;
+        let line = record.strip_suffix(separator).unwrap_or(record);
+        let open = self.delimiters > 0 || self.in_quotes || self.in_field;
+        if !open && matches!(line, b"" | b"\r") {
+            return;
+        }
+
+        let mut bytes = line.iter().peekable();
+        while let Some(&b) = bytes.next() {
+            if self.in_quotes {
+                if b == QUOTE {
+                    if bytes.peek() == Some(&&QUOTE) {
+                        bytes.next();
+                    } else {
+                        self.in_quotes = false;
+                    }
+                }
+            } else if b == delimited.delimiter {
+                self.delimiters += 1;
+                self.in_field = false;
+            } else {
+                // quotes anywhere but the start of a field are just text
+                self.in_quotes = delimited.quoting && b == QUOTE && !self.in_field;
+                self.in_field = true;
+            }
+        }
+
+        if !self.in_quotes {
+            self.end_record();
+        }
+    }
+
+    /// Settle state that depends on the end of input: a quote still open
+    /// ends with it.
+    pub fn finish(&mut self) {
+        self.scanned = true;
+        if self.in_quotes {
+            self.in_quotes = false;
+            self.end_record();
+        }
+    }
+
+    fn end_record(&mut self) {
+        let fields = self.delimiters + 1;
+        self.delimiters = 0;
+        self.in_field = false;
+
+        let header = *self.header_fields.get_or_insert(fields);
+        if fields != header {
+            self.mismatched += 1;
+        }
+        self.min_fields = if self.records == 0 {
+            fields
+        } else {
+            self.min_fields.min(fields)
+        };
+        self.max_fields = self.max_fields.max(fields);
+        self.records += 1;
+    }
+
+    pub fn cells(&self) -> [String; COLUMN_COUNT] {
+        [
+            self.min_fields.to_string(),
+            self.max_fields.to_string(),
+            self.mismatched.to_string(),
+        ]
+    }
+}
+
+impl AddAssign<&Self> for Fields {
+    fn add_assign(&mut self, rhs: &Self) {
+        if rhs.records > 0 {
+            self.header_fields = match (self.records, self.header_fields) {
+                (0, _) => rhs.header_fields,
+                (_, ours) if ours == rhs.header_fields => ours,
+                _ => None,
+            };
+            self.min_fields = if self.records == 0 {
+                rhs.min_fields
+            } else {
+                self.min_fields.min(rhs.min_fields)
+            };
+        }
+        self.max_fields = self.max_fields.max(rhs.max_fields);
+        self.mismatched += rhs.mismatched;
+        self.records += rhs.records;
+        self.scanned |= rhs.scanned;
+    }
+}
+
+#[test]
+fn test_scan_fields() {
+    let quoted = Delimited {
+        delimiter: b',',
+        quoting: true,
+    };
+    let mut fields = Fields::default();
+    for record in [
+        &b"name,note\r\n"[..],
+        b"a,\"x, \"\"y\"\"\n",
+        b"z\"\n",
+        b"\n",
+        b"b,c,d\n",
+        b"e",
+    ] {
+        fields.scan(record, b"\n", &quoted);
+    }
+    fields.finish();
+
+    // the quoted newline joins the second and third lines into one record
+    assert_eq!(fields.records, 4);
+    assert_eq!(fields.header_fields, Some(2));
+    assert_eq!((fields.min_fields, fields.max_fields), (1, 3));
+    assert_eq!(fields.mismatched, 2);
+
+    let mut unquoted = Fields::default();
+    let tsv = Delimited {
+        delimiter: b'\t',
+        quoting: false,
+    };
+    for record in [&b"\"a\tb\n"[..], b"c\"\td\n"] {
+        unquoted.scan(record, b"\n", &tsv);
+    }
+    assert_eq!((unquoted.records, unquoted.mismatched), (2, 0));
+}
@@ -0,0 +1,124 @@
+use std::io::Write;
+
+use crate::{CountOptions, FileCounts, RecordCounter};
+
+/// Counts data pushed to it in chunks of any size.
+///
+/// An unfinished record is held back until its separator (or `finish`)
+/// arrives, so words and UTF-8 sequences split across chunks count the same
+/// as with `FileCounts::from_reader`.
+pub struct Counter<'a> {
+    records: RecordCounter<'a>,
+    separator: &'a [u8],
+    pending: Vec<u8>,
+    // pending[..scanned] holds no start of a separator
+    scanned: usize,
+}
+
+impl<'a> Counter<'a> {
+    pub fn new(options: &'a CountOptions) -> Self {
+        Self {
+            records: RecordCounter::new(options),
+            separator: &options.record_separator,
+            pending: Vec::new(),
+            scanned: 0,
+        }
+    }
+
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.pending.extend_from_slice(chunk);
+        let separator = self.separator;
+        let mut start = 0;
+        let mut from = self.scanned;
+        while let Some(at) = self.pending[from..]
+            .windows(separator.len())
+            .position(|window| window == separator)
+        {
+            let end = from + at + separator.len();
+            self.records.record(&self.pending[start..end]);
+            start = end;
+            from = end;
+        }
+        self.pending.drain(..start);
+        // the tail may be the start of a separator completed by the next chunk
+        self.scanned = self.pending.len().saturating_sub(separator.len() - 1);
+    }
+
+    pub fn finish(mut self) -> FileCounts {
+        if !self.pending.is_empty() {
+            self.records.record(&self.pending);
+        }
+        self.records.finish()
+    }
+}
+
+/// Passes writes through to `inner`, counting whatever it accepted.
+pub struct CountingWriter<'a, W: Write> {
+    inner: W,
+    counter: Counter<'a>,
+}
+
+impl<'a, W: Write> CountingWriter<'a, W> {
+    pub fn new(inner: W, options: &'a CountOptions) -> Self {
+        Self {
+            inner,
+            counter: Counter::new(options),
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// The counts of everything written, and the inner writer.
+    pub fn finish(self) -> (FileCounts, W) {
+        (self.counter.finish(), self.inner)
+    }
+}
+
+impl<W: Write> Write for CountingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.counter.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[test]
+fn test_counter_chunks() {
+    let text = "Ünïcode wörds\r\nsplit   across\r\r\n\r\nchunks. Done!\r\nno end";
+    let options = CountOptions {
+        record_separator: b"\r\n".to_vec(),
+        line_endings: true,
+        prose: true,
+        ..CountOptions::default()
+    };
+    let (expected, _) = FileCounts::from_reader(text.as_bytes(), &options);
+    let expected = serde_json::to_string(&expected).unwrap();
+
+    for size in 1..8 {
+        let mut counter = Counter::new(&options);
+        for chunk in text.as_bytes().chunks(size) {
+            counter.update(chunk);
+        }
+        assert_eq!(
+            serde_json::to_string(&counter.finish()).unwrap(),
+            expected,
+            "chunks of {} bytes",
+            size
+        );
+    }
+
+    let mut writer = CountingWriter::new(Vec::new(), &options);
+    for chunk in text.as_bytes().chunks(5) {
+        writer.write_all(chunk).unwrap();
+    }
+    let (counts, written) = writer.finish();
+    assert_eq!(written, text.as_bytes());
+    assert_eq!(serde_json::to_string(&counts).unwrap(), expected);
+    assert_eq!((counts.lines, counts.words, counts.chars), (5, 8, 55));
+}
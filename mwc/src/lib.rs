@@ -0,0 +1,696 @@
+//! The counting core of mwc, for programs that want its counts without
+//! running the command.
+//!
+//! [`FileCounts::from_reader`] counts a whole stream; with the `async`
+//! feature, `count_async` does the same for a tokio `AsyncBufRead`.
+//! [`Counter`] takes data pushed in chunks instead, and [`CountingWriter`]
+//! counts what passes through it. All of them feed records through the same
+//! [`RecordCounter`], so they agree exactly. Its [`Progress`] between two
+//! records serializes, so a long count can be picked up again later.
+
+use std::{io::BufRead, ops::AddAssign, path::PathBuf};
+
+use clap::ValueEnum;
+use regex::bytes::Regex;
+use unicode_width::UnicodeWidthStr;
+
+#[cfg(feature = "async")]
+mod async_count;
+pub mod bytes_profile;
+pub mod fields;
+mod incremental;
+pub mod line_endings;
+pub mod per_line;
+pub mod prose;
+pub mod tokens;
+
+#[cfg(feature = "async")]
+pub use async_count::count_async;
+use bytes_profile::ByteProfile;
+use fields::Fields;
+pub use incremental::{Counter, CountingWriter};
+use line_endings::LineEndings;
+use per_line::LineCounts;
+
+pub const NEWLINE: u8 = 0x0a;
+pub const NUL: u8 = 0x00;
+
+/// What a `--count-matches` column counts for each line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MatchMode {
+    /// Number of lines containing at least one match
+    Lines,
+    /// Total number of non-overlapping matches
+    Occurrences,
+}
+
+/// Settings that change what gets counted, as opposed to how it's shown.
+pub struct CountOptions {
+    /// Each gets an entry in `FileCounts::matches`
+    pub patterns: Vec<Regex>,
+    pub match_mode: MatchMode,
+    /// Terminates records; never empty
+    pub record_separator: Vec<u8>,
+    pub line_endings: bool,
+    /// Count paragraphs and sentences
+    pub prose: bool,
+    /// Count tokens of this vocabulary
+    pub vocabulary: Option<tokens::Vocabulary>,
+    /// Tally every byte value
+    pub bytes_profile: bool,
+    /// Count the fields of delimited records
+    pub fields: Option<fields::Delimited>,
+    /// What a word is, if not a run of non-whitespace: every non-empty match
+    /// within a record (separator excluded) is one
+    pub word_pattern: Option<Regex>,
+    /// Leave out this many records at the start
+    pub skip_records: usize,
+    /// Count no more records than this after the skipped ones
+    pub max_records: Option<usize>,
+}
+
+impl Default for CountOptions {
+    fn default() -> Self {
+        Self {
+            patterns: Vec::new(),
+            match_mode: MatchMode::Lines,
+            record_separator: vec![NEWLINE],
+            line_endings: false,
+            prose: false,
+            vocabulary: None,
+            bytes_profile: false,
+            fields: None,
+            word_pattern: None,
+            skip_records: 0,
+            max_records: None,
+        }
+    }
+}
+
+impl CountOptions {
+    /// Options as they're given on the command line, checked and compiled
+    /// by [`CountOptionsBuilder::build`].
+    pub fn builder() -> CountOptionsBuilder {
+        CountOptionsBuilder::default()
+    }
+}
+
+/// Why a [`CountOptionsBuilder`] couldn't build its options.
+#[derive(thiserror::Error, Debug)]
+pub enum OptionsError {
+    #[error("invalid pattern '{0}': {1}")]
+    Pattern(String, regex::Error),
+    #[error("a word definition is either a character class or a regex, not both")]
+    WordDefinitions,
+    #[error("invalid word definition '{0}': {1}")]
+    WordPattern(String, regex::Error),
+    #[error("NUL-terminated records can't have another record separator")]
+    NullDataSeparator,
+    #[error("the record separator can't be empty")]
+    EmptySeparator,
+    #[error("a field delimiter or RFC 4180 quoting needs field counting")]
+    NoFields,
+    #[error("invalid vocabulary '{0}': {1}")]
+    Vocabulary(PathBuf, String),
+}
+
+/// Builds [`CountOptions`] from the source of patterns, word definitions and
+/// vocabularies; each setter is named after the mwc option it stands for.
+#[derive(Default)]
+pub struct CountOptionsBuilder {
+    patterns: Vec<String>,
+    fixed_strings: bool,
+    match_mode: Option<MatchMode>,
+    word_chars: Option<String>,
+    word_regex: Option<String>,
+    null_data: bool,
+    record_separator: Option<Vec<u8>>,
+    line_endings: bool,
+    prose: bool,
+    tokens: Option<PathBuf>,
+    bytes_profile: bool,
+    fields: bool,
+    delimiter: Option<u8>,
+    rfc4180: bool,
+    skip_records: usize,
+    max_records: Option<usize>,
+}
+
+impl CountOptionsBuilder {
+    /// Regular expressions counted into `FileCounts::matches`
+    pub fn patterns(mut self, patterns: Vec<String>) -> Self {
+        self.patterns = patterns;
+        self
+    }
+
+    /// Take the patterns literally
+    pub fn fixed_strings(mut self, fixed_strings: bool) -> Self {
+        self.fixed_strings = fixed_strings;
+        self
+    }
+
+    pub fn match_mode(mut self, match_mode: MatchMode) -> Self {
+        self.match_mode = Some(match_mode);
+        self
+    }
+
+    /// Words are runs of characters of this class, as inside `[]`
+    pub fn word_chars(mut self, class: Option<String>) -> Self {
+        self.word_chars = class;
+        self
+    }
+
+    /// Words are the matches of this regex
+    pub fn word_regex(mut self, regex: Option<String>) -> Self {
+        self.word_regex = regex;
+        self
+    }
+
+    /// Records end at NUL bytes
+    pub fn null_data(mut self, null_data: bool) -> Self {
+        self.null_data = null_data;
+        self
+    }
+
+    /// Records end at these bytes, rather than at newlines
+    pub fn record_separator(mut self, separator: Option<Vec<u8>>) -> Self {
+        self.record_separator = separator;
+        self
+    }
+
+    pub fn line_endings(mut self, line_endings: bool) -> Self {
+        self.line_endings = line_endings;
+        self
+    }
+
+    pub fn prose(mut self, prose: bool) -> Self {
+        self.prose = prose;
+        self
+    }
+
+    /// Count tokens of the vocabulary in this file
+    pub fn tokens(mut self, path: Option<PathBuf>) -> Self {
+        self.tokens = path;
+        self
+    }
+
+    pub fn bytes_profile(mut self, bytes_profile: bool) -> Self {
+        self.bytes_profile = bytes_profile;
+        self
+    }
+
+    /// Count the fields of delimited records
+    pub fn fields(mut self, fields: bool) -> Self {
+        self.fields = fields;
+        self
+    }
+
+    /// Fields are split at this byte instead of `,`
+    pub fn delimiter(mut self, delimiter: Option<u8>) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn rfc4180(mut self, rfc4180: bool) -> Self {
+        self.rfc4180 = rfc4180;
+        self
+    }
+
+    pub fn skip_records(mut self, skip_records: usize) -> Self {
+        self.skip_records = skip_records;
+        self
+    }
+
+    pub fn max_records(mut self, max_records: Option<usize>) -> Self {
+        self.max_records = max_records;
+        self
+    }
+
+    pub fn build(self) -> Result<CountOptions, OptionsError> {
+        let patterns = self
+            .patterns
+            .into_iter()
+            .map(|pattern| {
+                let source = if self.fixed_strings {
+                    regex::escape(&pattern)
+                } else {
+                    pattern.clone()
+                };
+                Regex::new(&source).map_err(|e| OptionsError::Pattern(pattern, e))
+            })
+            .collect::<Result<_, _>>()?;
+
+        // a class is shorthand for runs of its characters
+        let word_source = match (self.word_chars, self.word_regex) {
+            (Some(_), Some(_)) => return Err(OptionsError::WordDefinitions),
+            (Some(class), None) => Some((format!("[{}]+", class), class)),
+            (None, Some(regex)) => Some((regex.clone(), regex)),
+            (None, None) => None,
+        };
+        let word_pattern = word_source
+            .map(|(source, given)| {
+                Regex::new(&source).map_err(|e| OptionsError::WordPattern(given, e))
+            })
+            .transpose()?;
+
+        let record_separator = match (self.null_data, self.record_separator) {
+            (true, Some(_)) => return Err(OptionsError::NullDataSeparator),
+            (true, None) => vec![NUL],
+            (false, Some(separator)) if separator.is_empty() => {
+                return Err(OptionsError::EmptySeparator);
+            }
+            (false, Some(separator)) => separator,
+            (false, None) => vec![NEWLINE],
+        };
+
+        if !self.fields && (self.delimiter.is_some() || self.rfc4180) {
+            return Err(OptionsError::NoFields);
+        }
+        let fields = self.fields.then(|| fields::Delimited {
+            delimiter: self.delimiter.unwrap_or(b','),
+            quoting: self.rfc4180,
+        });
+
+        let vocabulary = self
+            .tokens
+            .map(|path| {
+                tokens::Vocabulary::load(&path).map_err(|e| OptionsError::Vocabulary(path, e))
+            })
+            .transpose()?;
+
+        Ok(CountOptions {
+            patterns,
+            match_mode: self.match_mode.unwrap_or(MatchMode::Lines),
+            record_separator,
+            line_endings: self.line_endings,
+            prose: self.prose,
+            vocabulary,
+            bytes_profile: self.bytes_profile,
+            fields,
+            word_pattern,
+            skip_records: self.skip_records,
+            max_records: self.max_records,
+        })
+    }
+}
+
+#[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FileCounts {
+    pub lines: usize,
+    pub words: usize,
+    pub bytes: usize,
+    pub chars: usize,
+    pub max_line_length: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matches: Vec<usize>,
+    #[serde(default, skip_serializing_if = "LineEndings::is_unscanned")]
+    pub line_endings: LineEndings,
+    /// Bytes read before decompression, only tracked with --decompress
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub compressed_bytes: usize,
+    /// Only tracked with `CountOptions::prose`
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub paragraphs: usize,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub sentences: usize,
+    /// Only tracked with `CountOptions::vocabulary`
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub tokens: usize,
+    /// Bytes of text the vocabulary's pattern failed to split, each counted
+    /// as a token of its own
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub unsplit_bytes: usize,
+    /// Only tracked with `CountOptions::bytes_profile`
+    #[serde(default, skip_serializing_if = "ByteProfile::is_unscanned")]
+    pub bytes_profile: ByteProfile,
+    /// Only tracked with `CountOptions::fields`
+    #[serde(default, skip_serializing_if = "Fields::is_unscanned")]
+    pub fields: Fields,
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+// Like `BufRead::read_until`, but the delimiter may span several bytes.
+// The separator is included in `buf` when it was found, just like read_until.
+fn read_record(
+    reader: &mut impl BufRead,
+    separator: &[u8],
+    buf: &mut Vec<u8>,
+) -> std::io::Result<usize> {
+    let last = *separator.last().expect("record separator is never empty");
+    let mut total = 0;
+    loop {
+        let bytes_read = reader.read_until(last, buf)?;
+        total += bytes_read;
+        if bytes_read == 0 || buf.last() != Some(&last) || buf.ends_with(separator) {
+            return Ok(total);
+        }
+    }
+}
+
+impl FileCounts {
+    pub fn words_per_sentence(&self) -> Option<f64> {
+        (self.sentences > 0).then(|| self.words as f64 / self.sentences as f64)
+    }
+
+    pub fn new(options: &CountOptions) -> Self {
+        Self {
+            matches: vec![0; options.patterns.len()],
+            ..Self::default()
+        }
+    }
+
+    // wc behavior: Always returns partial counts collected before stream ends.
+    // A read error (e.g. a corrupt compressed stream) stops counting and is
+    // returned alongside whatever was successfully counted up to that point.
+    // File-level errors (permissions, not found) are handled separately at
+    // the file opening stage.
+    pub fn from_reader(
+        reader: impl BufRead,
+        options: &CountOptions,
+    ) -> (Self, std::io::Result<()>) {
+        Self::from_reader_with(reader, options, |_| {})
+    }
+
+    /// Like `from_reader`, also handing the counts of every record to `on_record`.
+    pub fn from_reader_with(
+        reader: impl BufRead,
+        options: &CountOptions,
+        mut on_record: impl FnMut(&LineCounts),
+    ) -> (Self, std::io::Result<()>) {
+        let mut counter = RecordCounter::new(options);
+        let result = counter.read_from(reader, |_, record| on_record(record));
+        (counter.finish(), result)
+    }
+}
+
+/// Where a [`RecordCounter`] is between two records. It serializes, so
+/// counting can carry on from it in another process.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Progress {
+    counts: FileCounts,
+    prose: prose::Scanner,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tokens: Option<tokens::TokenProgress>,
+    terminated: bool,
+    #[serde(default)]
+    seen: usize,
+    #[serde(default)]
+    consumed: u64,
+}
+
+/// Counts a stream one record at a time, whatever reads the records.
+///
+/// Each record is passed whole, including its separator unless it's the
+/// unterminated last one. Records outside `skip_records` and `max_records`
+/// are passed too, but not counted.
+pub struct RecordCounter<'a> {
+    options: &'a CountOptions,
+    counts: FileCounts,
+    prose: prose::Scanner,
+    tokens: Option<tokens::TokenStream<'a>>,
+    terminated: bool,
+    // records and bytes passed, counted or not
+    seen: usize,
+    consumed: u64,
+    past_start: bool,
+}
+
+impl<'a> RecordCounter<'a> {
+    pub fn new(options: &'a CountOptions) -> Self {
+        Self {
+            options,
+            counts: FileCounts::new(options),
+            prose: prose::Scanner::default(),
+            tokens: options.vocabulary.as_ref().map(tokens::TokenStream::new),
+            terminated: true,
+            seen: 0,
+            consumed: 0,
+            past_start: false,
+        }
+    }
+
+    /// For records that begin partway into the input, as with a byte range,
+    /// so the first isn't checked for a byte order mark.
+    pub fn past_start(mut self) -> Self {
+        self.past_start = true;
+        self
+    }
+
+    /// Carries on from `progress`, which must come from counting with the
+    /// same `options`.
+    pub fn resume(options: &'a CountOptions, progress: Progress) -> Self {
+        let token_progress = progress.tokens.unwrap_or_default();
+        Self {
+            options,
+            counts: progress.counts,
+            prose: progress.prose,
+            tokens: options
+                .vocabulary
+                .as_ref()
+                .map(|vocabulary| tokens::TokenStream::resume(vocabulary, token_progress)),
+            terminated: progress.terminated,
+            seen: progress.seen,
+            consumed: progress.consumed,
+            past_start: false,
+        }
+    }
+
+    pub fn progress(&self) -> Progress {
+        Progress {
+            counts: self.counts.clone(),
+            prose: self.prose.clone(),
+            tokens: self.tokens.as_ref().map(tokens::TokenStream::progress),
+            terminated: self.terminated,
+            seen: self.seen,
+            consumed: self.consumed,
+        }
+    }
+
+    /// Bytes of all the records passed so far, counted or not.
+    pub fn consumed(&self) -> u64 {
+        self.consumed
+    }
+
+    /// True once `max_records` have been counted, so the rest of the input
+    /// needn't be read.
+    pub fn is_done(&self) -> bool {
+        self.options
+            .max_records
+            .is_some_and(|max| self.counts.lines >= max)
+    }
+
+    /// Counts the records of `reader` up to `max_records`, handing the counts
+    /// of each to `on_record` along with the counter, whose `progress` can be
+    /// taken there.
+    pub fn read_from(
+        &mut self,
+        mut reader: impl BufRead,
+        mut on_record: impl FnMut(&Self, &LineCounts),
+    ) -> std::io::Result<()> {
+        let mut buf = Vec::new();
+        while !self.is_done() {
+            if read_record(&mut reader, &self.options.record_separator, &mut buf)? == 0 {
+                break;
+            }
+            if let Some(line_counts) = self.record(&buf) {
+                on_record(self, &line_counts);
+            }
+            buf.clear();
+        }
+        Ok(())
+    }
+
+    /// The counts of `record`, unless it's skipped or past `max_records`.
+    pub fn record(&mut self, record: &[u8]) -> Option<LineCounts> {
+        if self.seen == 0 && self.options.line_endings {
+            // a byte order mark is only one at the start, skipped or not
+            let first = if self.past_start { &[][..] } else { record };
+            self.counts.line_endings.begin(first);
+        }
+        self.seen += 1;
+        self.consumed += record.len() as u64;
+        if self.seen <= self.options.skip_records || self.is_done() {
+            return None;
+        }
+
+        let options = self.options;
+        let counts = &mut self.counts;
+        let separator = options.record_separator.as_slice();
+        let line = record.strip_suffix(separator).unwrap_or(record);
+        let line_counts = LineCounts {
+            line: self.seen,
+            words: match options.word_pattern {
+                Some(ref pattern) => pattern.find_iter(line).filter(|m| !m.is_empty()).count(),
+                None => record
+                    .split(u8::is_ascii_whitespace)
+                    .filter(|s| !s.is_empty())
+                    .count(),
+            },
+            bytes: record.len(),
+            chars: std::str::from_utf8(record)
+                .map(|s| s.chars().count())
+                .unwrap_or(0),
+            width: std::str::from_utf8(line).map(|s| s.width()).unwrap_or(0),
+        };
+        counts.bytes += line_counts.bytes;
+        counts.lines += 1;
+        counts.words += line_counts.words;
+        counts.chars += line_counts.chars;
+        counts.max_line_length = counts.max_line_length.max(line_counts.width);
+        for (count, pattern) in counts.matches.iter_mut().zip(&options.patterns) {
+            *count += match options.match_mode {
+                MatchMode::Lines => pattern.is_match(line) as usize,
+                MatchMode::Occurrences => pattern.find_iter(line).count(),
+            };
+        }
+        if options.line_endings {
+            counts.line_endings.scan(record);
+        }
+        if options.prose {
+            self.prose.scan(line);
+        }
+        if let Some(ref mut tokens) = self.tokens {
+            tokens.scan(record);
+        }
+        if options.bytes_profile {
+            counts.bytes_profile.scan(record);
+        }
+        if let Some(ref delimited) = options.fields {
+            counts.fields.scan(record, separator, delimited);
+        }
+        self.terminated = record.ends_with(separator);
+        Some(line_counts)
+    }
+
+    pub fn finish(mut self) -> FileCounts {
+        if self.options.line_endings {
+            self.counts.line_endings.finish(self.terminated);
+        }
+        if self.options.bytes_profile && self.counts.bytes_profile.is_unscanned() {
+            // empty inputs still get a profile, saying so
+            self.counts.bytes_profile.scan(&[]);
+        }
+        if self.options.fields.is_some() {
+            self.counts.fields.finish();
+        }
+        if self.options.prose {
+            self.prose.finish();
+            self.counts.paragraphs = self.prose.paragraphs;
+            self.counts.sentences = self.prose.sentences;
+        }
+        if let Some(mut tokens) = self.tokens {
+            tokens.finish();
+            self.counts.tokens = tokens.tokens;
+            self.counts.unsplit_bytes = tokens.unsplit;
+        }
+        self.counts
+    }
+}
+
+impl AddAssign<&Self> for FileCounts {
+    fn add_assign(&mut self, rhs: &Self) {
+        self.lines += rhs.lines;
+        self.bytes += rhs.bytes;
+        self.words += rhs.words;
+        self.chars += rhs.chars;
+        self.max_line_length = self.max_line_length.max(rhs.max_line_length);
+        if self.matches.len() < rhs.matches.len() {
+            self.matches.resize(rhs.matches.len(), 0);
+        }
+        for (count, rhs_count) in self.matches.iter_mut().zip(&rhs.matches) {
+            *count += rhs_count;
+        }
+        self.line_endings += &rhs.line_endings;
+        self.compressed_bytes += rhs.compressed_bytes;
+        self.paragraphs += rhs.paragraphs;
+        self.sentences += rhs.sentences;
+        self.tokens += rhs.tokens;
+        self.unsplit_bytes += rhs.unsplit_bytes;
+        self.bytes_profile += &rhs.bytes_profile;
+        self.fields += &rhs.fields;
+    }
+}
+
+#[test]
+fn test_resume_progress() {
+    let options = CountOptions {
+        // so a CR ends each record, and whether an LF follows is left pending
+        record_separator: vec![b'\r'],
+        line_endings: true,
+        prose: true,
+        bytes_profile: true,
+        ..CountOptions::default()
+    };
+    let data = b"One. Two\r\nthree.\r\n\r\nFour \xff";
+    let whole = FileCounts::from_reader(&data[..], &options).0;
+
+    let records: Vec<&[u8]> = data.split_inclusive(|&b| b == b'\r').collect();
+    for at in 0..=records.len() {
+        let mut counter = RecordCounter::new(&options);
+        for record in &records[..at] {
+            counter.record(record);
+        }
+        let saved = serde_json::to_string(&counter.progress()).unwrap();
+        let mut counter = RecordCounter::resume(&options, serde_json::from_str(&saved).unwrap());
+        for record in &records[at..] {
+            counter.record(record);
+        }
+        assert_eq!(
+            serde_json::to_value(counter.finish()).unwrap(),
+            serde_json::to_value(&whole).unwrap()
+        );
+    }
+}
+
+#[test]
+fn test_skip_and_max_records() {
+    let options = CountOptions {
+        skip_records: 1,
+        max_records: Some(2),
+        ..CountOptions::default()
+    };
+    let mut lines = Vec::new();
+    let mut counter = RecordCounter::new(&options);
+    let data = b"header\none two\nthree\nfour\nfive\n";
+    counter
+        .read_from(&data[..], |_, record| lines.push(record.line))
+        .unwrap();
+    assert!(counter.is_done());
+    // line numbers still count from the start of the input
+    assert_eq!(lines, [2, 3]);
+    let counts = counter.finish();
+    assert_eq!((counts.lines, counts.words, counts.bytes), (2, 3, 14));
+}
+
+#[test]
+fn test_options_builder() {
+    let options = CountOptions::builder()
+        .patterns(vec!["a.b".into()])
+        .fixed_strings(true)
+        .word_chars(Some("[:alpha:]".into()))
+        .fields(true)
+        .rfc4180(true)
+        .build()
+        .unwrap();
+    let counts = FileCounts::from_reader(&b"a.b,axb\n"[..], &options).0;
+    assert_eq!((counts.matches[0], counts.words), (1, 3));
+    assert_eq!(counts.fields.max_fields, 2);
+
+    let invalid = [
+        CountOptions::builder().patterns(vec!["(".into()]),
+        CountOptions::builder()
+            .word_chars(Some("a".into()))
+            .word_regex(Some("b".into())),
+        CountOptions::builder()
+            .null_data(true)
+            .record_separator(Some(vec![b';'])),
+        CountOptions::builder().record_separator(Some(Vec::new())),
+        CountOptions::builder().rfc4180(true),
+    ];
+    for builder in invalid {
+        assert!(builder.build().is_err());
+    }
+}
@@ -0,0 +1,184 @@
+use std::{fmt::Display, ops::AddAssign};
+
+use serde::{Deserialize, Serialize};
+
+const LF: u8 = b'\n';
+const CR: u8 = b'\r';
+
+/// Byte order mark found at the very start of an input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Bom {
+    None,
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    // Totals over inputs that disagree
+    Mixed,
+}
+
+impl Bom {
+    fn detect(start: &[u8]) -> Self {
+        if start.starts_with(&[0xEF, 0xBB, 0xBF]) {
+            Bom::Utf8
+        } else if start.starts_with(&[0xFF, 0xFE]) {
+            Bom::Utf16Le
+        } else if start.starts_with(&[0xFE, 0xFF]) {
+            Bom::Utf16Be
+        } else {
+            Bom::None
+        }
+    }
+}
+
+impl Display for Bom {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Bom::None => write!(f, "none"),
+            Bom::Utf8 => write!(f, "utf-8"),
+            Bom::Utf16Le => write!(f, "utf-16le"),
+            Bom::Utf16Be => write!(f, "utf-16be"),
+            Bom::Mixed => write!(f, "mixed"),
+        }
+    }
+}
+
+/// Diagnostics explaining why two "identical" files count differently.
+///
+/// Records are fed in order via [`LineEndings::scan`]; a CR at the end of one
+/// record is only classified once the next record (or the end of input) shows
+/// whether an LF follows it.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct LineEndings {
+    pub lf: usize,
+    pub crlf: usize,
+    pub cr: usize,
+    // 1 per input whose final record has no terminator, so totals count files
+    pub unterminated: usize,
+    // `None` until an input has been scanned
+    pub bom: Option<Bom>,
+    pub invalid_utf8: usize,
+    pub nul: usize,
+    // only ever set mid-stream, as in a `Progress`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pending_cr: bool,
+}
+
+pub const COLUMN_COUNT: usize = 7;
+
+/// `--header` labels, in the order of [`LineEndings::cells`].
+pub const LABELS: [&str; COLUMN_COUNT] = [
+    "lf",
+    "crlf",
+    "cr",
+    "unterminated",
+    "bom",
+    "invalid_utf8",
+    "nul",
+];
+
+impl LineEndings {
+    /// True unless --line-endings was in effect for these counts.
+    pub fn is_unscanned(&self) -> bool {
+        self.bom.is_none()
+    }
+
+    /// Checks the first record for a byte order mark, given empty when
+    /// counting starts past the beginning of the input.
+    pub fn begin(&mut self, first: &[u8]) {
+        self.bom = Some(Bom::detect(first));
+    }
+
+    pub fn scan(&mut self, record: &[u8]) {
+        let mut bytes = record.iter().peekable();
+        if self.pending_cr {
+            self.pending_cr = false;
+            if bytes.peek() == Some(&&LF) {
+                bytes.next();
+                self.crlf += 1;
+            } else {
+                self.cr += 1;
+            }
+        }
+        while let Some(&b) = bytes.next() {
+            match b {
+                LF => self.lf += 1,
+                CR => match bytes.peek() {
+                    Some(&&LF) => {
+                        bytes.next();
+                        self.crlf += 1;
+                    }
+                    Some(_) => self.cr += 1,
+                    None => self.pending_cr = true,
+                },
+                0 => self.nul += 1,
+                _ => {}
+            }
+        }
+
+        self.invalid_utf8 += record
+            .utf8_chunks()
+            .filter(|chunk| !chunk.invalid().is_empty())
+            .count();
+    }
+
+    /// Settle state that depends on the end of input.
+    pub fn finish(&mut self, terminated: bool) {
+        if self.pending_cr {
+            self.pending_cr = false;
+            self.cr += 1;
+        }
+        if self.bom.is_none() {
+            self.bom = Some(Bom::None);
+        }
+        if !terminated {
+            self.unterminated += 1;
+        }
+    }
+
+    pub fn cells(&self) -> [String; COLUMN_COUNT] {
+        [
+            self.lf.to_string(),
+            self.crlf.to_string(),
+            self.cr.to_string(),
+            self.unterminated.to_string(),
+            self.bom
+                .map_or_else(|| "-".to_string(), |bom| bom.to_string()),
+            self.invalid_utf8.to_string(),
+            self.nul.to_string(),
+        ]
+    }
+}
+
+impl AddAssign<&Self> for LineEndings {
+    fn add_assign(&mut self, rhs: &Self) {
+        self.lf += rhs.lf;
+        self.crlf += rhs.crlf;
+        self.cr += rhs.cr;
+        self.unterminated += rhs.unterminated;
+        self.invalid_utf8 += rhs.invalid_utf8;
+        self.nul += rhs.nul;
+        self.bom = match (self.bom, rhs.bom) {
+            (None, other) | (other, None) => other,
+            (Some(ours), Some(theirs)) if ours == theirs => Some(ours),
+            _ => Some(Bom::Mixed),
+        };
+    }
+}
+
+#[test]
+fn test_scan_line_endings() {
+    let mut endings = LineEndings::default();
+    endings.begin(b"\xEF\xBB\xBFa\r\n");
+    for record in [&b"\xEF\xBB\xBFa\r\n"[..], b"b\rc\n", b"d\r", b"\ne\0\xff"] {
+        endings.scan(record);
+    }
+    endings.finish(false);
+
+    assert_eq!(endings.lf, 1);
+    assert_eq!(endings.crlf, 2);
+    assert_eq!(endings.cr, 1);
+    assert_eq!(endings.unterminated, 1);
+    assert_eq!(endings.bom, Some(Bom::Utf8));
+    assert_eq!(endings.invalid_utf8, 1);
+    assert_eq!(endings.nul, 1);
+}
@@ -1,15 +1,74 @@
 use std::{
+    ffi::OsString,
     fmt::Display,
-    io::{BufRead, BufReader},
-    ops::AddAssign,
+    io::{BufRead, BufReader, Read, Seek, SeekFrom},
     path::{Path, PathBuf},
 };
 
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches, Parser, ValueEnum};
+use regex::bytes::Regex;
 use unicode_width::UnicodeWidthStr;
 
+use color::{ColorChoice, Style};
+use compat::Conformance;
+#[cfg(test)]
+use line_endings::LineEndings;
+use mwc::{
+    CountOptions, FileCounts, MatchMode, NUL, Progress, RecordCounter, bytes_profile, fields,
+    line_endings, per_line, tokens,
+};
+use numbers::{NumberFormat, Quantity, Units};
+
+mod archive;
+mod cache;
+mod check;
+mod checkpoint;
+mod color;
+mod compat;
+mod decompress;
+mod defaults;
+mod numbers;
+mod passthrough;
+mod report;
+
+// Exit status when counting worked but --max-* or --max-growth limits didn't hold
+const EXIT_LIMITS_EXCEEDED: i32 = 3;
+
 fn main() -> std::io::Result<()> {
-    let mut opts = Opts::parse();
+    let mut command = Opts::command();
+    command.build();
+    let cli: Vec<OsString> = std::env::args_os().collect();
+    let layers = if cli
+        .iter()
+        .take_while(|arg| *arg != "--")
+        .any(|arg| arg == "--no-config")
+    {
+        Vec::new()
+    } else {
+        defaults::load(&command).unwrap_or_else(|e| {
+            command
+                .error(clap::error::ErrorKind::InvalidValue, e)
+                .exit()
+        })
+    };
+    let matches = command
+        .clone()
+        .get_matches_from(defaults::apply(&command, cli, &layers));
+    let mut opts = Opts::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let display = CountsConfig::try_from(&opts).unwrap_or_else(|e| {
+        Opts::command()
+            .error(clap::error::ErrorKind::ValueValidation, e)
+            .exit()
+    });
+    if opts.print_config {
+        for layer in &layers {
+            println!("# with defaults from {}", layer.origin);
+        }
+        let mut settings = display.settings();
+        defaults::complete(&command, &matches, &mut settings);
+        print!("{}", settings);
+        return Ok(());
+    }
     let inputs = if let Some(ref inputs_input) = opts.files_from {
         let mut reader = inputs_input
             .reader()
@@ -32,55 +91,247 @@ fn main() -> std::io::Result<()> {
     } else {
         std::mem::take(&mut opts.inputs)
     };
+    let baseline = opts.compare.as_ref().map(|path| {
+        report::Report::load(path).unwrap_or_else(|e| {
+            eprintln!("mwc: {}: cannot read baseline: {}", path.display(), e);
+            std::process::exit(1)
+        })
+    });
+    // with --passthrough, stdout carries the data instead
+    let destination: Box<dyn std::io::Write> = match opts.report {
+        Some(ref path) => match std::fs::File::create(path) {
+            Ok(file) => Box::new(std::io::BufWriter::new(file)),
+            Err(e) => {
+                eprintln!("mwc: {}: cannot write report: {}", path.display(), e);
+                std::process::exit(1)
+            }
+        },
+        None if opts.passthrough => Box::new(std::io::stderr()),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    let limits = check::Limits {
+        max_lines: opts.max_lines,
+        max_line_length: opts.max_width,
+        max_bytes: opts.max_bytes,
+    };
 
-    let mut counter = Counter::new();
+    let mut counter = Counter::new(&display);
+    if let Some(ref path) = opts.cache {
+        match cache::Cache::open(path, display.fingerprint()) {
+            Ok(cache) => counter.cache = Some(cache),
+            Err(e) => eprintln!("mwc: {}: cache unavailable: {}", path.display(), e),
+        }
+    }
+    // counts depend on these, and so does which rows there are
+    let settings = format!("{} archive={}", display.fingerprint(), display.archive);
+    let input_paths: Vec<PathBuf> = inputs.iter().map(Input::path).collect();
+    if let Some(ref path) = opts.resume {
+        match checkpoint::load(path, &settings, &input_paths) {
+            Ok(state) => counter.restore(state),
+            // the checkpoint of a run that finished is gone: start afresh, so
+            // a restarted job can run the very same command
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                eprintln!("mwc: {}: cannot resume: {}", path.display(), e);
+                std::process::exit(1)
+            }
+        }
+    }
+    if let Some(ref path) = opts.checkpoint {
+        counter.checkpoint = Some(checkpoint::Writer::new(
+            path.clone(),
+            std::time::Duration::from_secs(opts.checkpoint_interval),
+            settings,
+            input_paths,
+        ));
+    }
     let input_count = inputs.len();
-    let display = (&opts).into();
+    let uniform_width = match display.conformance {
+        Conformance::Mwc => None,
+        Conformance::Posix => Some(0),
+        Conformance::Gnu => Some(compat::gnu_width(
+            &inputs,
+            display.columns().len() + display.extra_labels().len(),
+        )),
+    };
 
     // If you provide no inputs wc will try to read from stdin.
     // You can type whatever and then hit Ctrl-D to get the wc stats for what you typed.
     if input_count == 0 {
         counter.count_stdin();
     } else {
-        for input in inputs {
+        let skipped = counter.inputs_done;
+        for input in inputs.into_iter().skip(skipped) {
             counter.count_input(input);
         }
     }
 
     let mut output = Printer {
-        display,
+        display: &display,
         widths: counter.widths.clone(),
+        uniform_width,
     };
+    let error_color = opts.color.enabled_for(&std::io::stderr());
     let mut any_errors = false;
-    let mut stdout = IoToFmt::stdout();
+    let mut limits_exceeded = false;
+    let mut out = IoToFmt(destination);
+    let mut rows = Vec::new();
     for result in counter.file_counts {
         match result {
-            Ok(CountedInput(CountsName::StdIn, file_counts)) if input_count < 2 => {
-                output
-                    .print_result(&mut stdout, CountsName::Blank, file_counts)
-                    .expect("TODO: HANDLE ME");
-            }
-            Ok(CountedInput(file, file_count)) => {
-                output
-                    .print_result(&mut stdout, file, file_count)
-                    .expect("TODO: HANDLE ME");
+            Ok(CountedInput(name, file_counts)) => {
+                for violation in limits.violations(&file_counts) {
+                    let message = format!("mwc: {}: {}", name.path().display(), violation);
+                    eprintln!("{}", color::paint(&message, Style::Red, error_color));
+                    limits_exceeded = true;
+                }
+                rows.push((name, file_counts));
             }
             Err(error) => {
-                eprintln!("{}", error);
+                eprintln!(
+                    "{}",
+                    color::paint(&error.to_string(), Style::Red, error_color)
+                );
                 any_errors = true;
             }
         }
     }
 
-    if input_count > 1 {
-        output.widths = counter.widths.max((&counter.totals).into());
-        output
-            .print_result(&mut stdout, CountsName::Total, counter.totals)
-            .expect("TODO: HANDLE ME");
+    // --top alone means "the largest N" by the first shown column
+    let sort = opts.sort.or_else(|| {
+        opts.top.map(|_| SortKey {
+            field: display
+                .columns()
+                .first()
+                .map_or(Field::Name, |&column| Field::Count(column)),
+            descending: true,
+        })
+    });
+    if let Some(sort) = sort {
+        rows.sort_by(|a, b| sort.compare(a, b));
+    }
+    if let Some(top) = opts.top {
+        rows.truncate(top);
+    }
+
+    if let Some(ref baseline) = baseline {
+        let comparison = check::compare(
+            baseline,
+            rows.iter()
+                .map(|(name, counts)| (name.path().display().to_string(), counts)),
+            &counter.totals,
+            &display.columns(),
+            opts.max_growth,
+        );
+        for line in comparison.lines {
+            writeln!(out.0, "{}", line)?;
+        }
+        limits_exceeded |= comparison.exceeded;
+    } else if opts.format == OutputFormat::Json {
+        let report = report::Report {
+            patterns: display.patterns(),
+            files: rows
+                .into_iter()
+                .map(|(name, counts)| report::ReportEntry {
+                    name: name.path().display().to_string(),
+                    counts,
+                })
+                .collect(),
+            total: counter.totals,
+        };
+        report.write(&mut out.0)?;
+        writeln!(out.0)?;
+    } else if opts.format == OutputFormat::Ndjson {
+        let patterns = display.patterns();
+        for (name, counts) in &rows {
+            report::Record::File {
+                name: &name.path().display().to_string(),
+                patterns: &patterns,
+                counts,
+            }
+            .write_line(&mut out.0)?;
+        }
+        report::Record::Total {
+            patterns: &patterns,
+            counts: &counter.totals,
+        }
+        .write_line(&mut out.0)?;
+    } else {
+        let show_total = input_count > 1 || counter.counted > 1;
+        let rows: Vec<_> = rows
+            .into_iter()
+            .map(|(name, counts)| match name {
+                _ if opts.no_filename => (CountsName::Blank, counts),
+                CountsName::StdIn if input_count < 2 => (CountsName::Blank, counts),
+                _ => (name, counts),
+            })
+            .collect();
+        let names_shown = show_total
+            || rows
+                .iter()
+                .any(|(name, _)| !matches!(name, CountsName::Blank));
+        output.widths.name = rows
+            .iter()
+            .map(|(name, _)| name.to_string().width())
+            .chain(show_total.then(|| CountsName::Total.to_string().width()))
+            .chain((opts.header && names_shown).then(|| NAME_LABEL.width()))
+            .max()
+            .unwrap_or(0);
+        if opts.header {
+            output.widths = output.widths.max(ColumnWidths::labels(&display));
+            output
+                .print_header(&mut out, names_shown)
+                .map_err(std::io::Error::other)?;
+        }
+        let mut profiles = Vec::new();
+        for (name, file_counts) in rows {
+            if display.counting.bytes_profile {
+                profiles.push((name.path(), file_counts.bytes_profile.clone()));
+            }
+            output
+                .print_result(&mut out, name, file_counts)
+                .map_err(std::io::Error::other)?;
+        }
+        if show_total {
+            output.widths = output
+                .widths
+                .max(ColumnWidths::new(&counter.totals, &display.numbers));
+            output
+                .print_total(&mut out, &counter.totals)
+                .map_err(std::io::Error::other)?;
+        }
+        for (path, profile) in profiles {
+            print_byte_table(&mut out, &path, &profile).map_err(std::io::Error::other)?;
+        }
+    }
+    out.0.flush()?;
+
+    if let Some(ref checkpoint) = counter.checkpoint
+        && let Err(e) = checkpoint.remove()
+    {
+        eprintln!(
+            "mwc: {}: checkpoint not removed: {}",
+            checkpoint.path().display(),
+            e
+        );
+    }
+
+    if let Some(ref mut cache) = counter.cache {
+        if let Err(e) = cache.save() {
+            eprintln!("mwc: {}: cache not saved: {}", cache.path().display(), e);
+        }
+        if opts.cache_stats {
+            let stats = cache.stats;
+            eprintln!(
+                "mwc: cache: {} hits, {} misses, {} stored",
+                stats.hits, stats.misses, stats.stored
+            );
+        }
     }
 
     if any_errors {
         std::process::exit(1)
+    } else if limits_exceeded {
+        std::process::exit(EXIT_LIMITS_EXCEEDED)
     } else {
         Ok(())
     }
@@ -94,39 +345,135 @@ enum Error {
     IsDirectory(PathBuf),
     #[error("mwc: {0}: File read error: {1}")]
     FileCount(PathBuf, std::io::Error),
+    #[error(transparent)]
+    Options(#[from] mwc::OptionsError),
+    #[error("the tokens column needs --tokens=VOCAB_FILE")]
+    NoVocabulary,
+    #[error("--per-line can't be combined with --format=json; use --format=ndjson")]
+    PerLineJson,
+    #[error("{0} is a GNU extension, which --posix and POSIXLY_CORRECT don't allow")]
+    NotPosix(&'static str),
+    #[error("-c and -m can't be combined under --posix or POSIXLY_CORRECT")]
+    PosixBytesAndChars,
+    // reported before a --checkpoint, and reported again after --resume
+    #[error("{0}")]
+    Resumed(String),
+    #[error("mwc: stdout: {0}")]
+    Passthrough(std::io::Error),
 }
 
 struct CountedInput(CountsName, FileCounts);
 
-struct Counter {
+struct Counter<'a> {
+    config: &'a CountsConfig,
     file_counts: Vec<Result<CountedInput, Error>>,
     widths: ColumnWidths,
     totals: FileCounts,
     stdin_seen: bool,
+    // rows of counts, which with --archive can exceed the number of inputs
+    counted: usize,
+    cache: Option<cache::Cache>,
+    checkpoint: Option<checkpoint::Writer>,
+    // inputs counted so far, including those restored by --resume
+    inputs_done: usize,
+    // where --resume found counting stopped inside the next input
+    resume: Option<checkpoint::Partial>,
 }
 
-impl Counter {
-    fn new() -> Self {
+impl<'a> Counter<'a> {
+    fn new(config: &'a CountsConfig) -> Self {
         Self {
+            config,
             file_counts: Vec::new(),
             widths: ColumnWidths::default(),
             totals: FileCounts::default(),
             stdin_seen: false,
+            counted: 0,
+            cache: None,
+            checkpoint: None,
+            inputs_done: 0,
+            resume: None,
         }
     }
 
+    /// Picks up where a `--checkpoint` left off.
+    fn restore(&mut self, state: checkpoint::State) {
+        for row in state.rows {
+            let result = match row {
+                checkpoint::Row::Counted { name, counts } => {
+                    self.widths = self
+                        .widths
+                        .max(ColumnWidths::new(&counts, &self.config.numbers));
+                    Ok(CountedInput(name, *counts))
+                }
+                checkpoint::Row::Failed { message } => Err(Error::Resumed(message)),
+            };
+            self.file_counts.push(result);
+        }
+        self.totals = state.totals;
+        self.counted = state.counted;
+        self.stdin_seen = state.stdin_seen;
+        self.inputs_done = state.next_input;
+        self.resume = state.current;
+    }
+
     fn count_input(&mut self, input: Input) {
+        let resume = self.resume.take();
         match input {
-            Input::File(path_buf) => self.count_file(path_buf),
+            Input::File(path_buf) => self.count_file(path_buf, resume),
             Input::StdIn if !self.stdin_seen => {
                 self.count_stdin();
                 self.stdin_seen = true;
             }
             Input::StdIn => self.count_default(),
         }
+        self.inputs_done += 1;
+        if self
+            .checkpoint
+            .as_ref()
+            .is_some_and(checkpoint::Writer::due)
+        {
+            self.save_checkpoint(None);
+        }
     }
 
-    fn count_file(&mut self, pb: PathBuf) {
+    // Only ever called between inputs or, for regular files, between records,
+    // when every row so far belongs to a finished input.
+    fn save_checkpoint(&self, current: Option<checkpoint::Partial>) {
+        let Some(ref writer) = self.checkpoint else {
+            return;
+        };
+        let rows = self
+            .file_counts
+            .iter()
+            .map(|result| match result {
+                Ok(CountedInput(name, counts)) => checkpoint::Row::Counted {
+                    name: name.clone(),
+                    counts: Box::new(counts.clone()),
+                },
+                Err(error) => checkpoint::Row::Failed {
+                    message: error.to_string(),
+                },
+            })
+            .collect();
+        let state = checkpoint::State {
+            rows,
+            totals: self.totals.clone(),
+            counted: self.counted,
+            stdin_seen: self.stdin_seen,
+            next_input: self.inputs_done,
+            current,
+        };
+        if let Err(e) = writer.save(state) {
+            eprintln!(
+                "mwc: {}: checkpoint not saved: {}",
+                writer.path().display(),
+                e
+            );
+        }
+    }
+
+    fn count_file(&mut self, pb: PathBuf, resume: Option<checkpoint::Partial>) {
         if !pb.exists() {
             self.file_counts.push(Err(Error::NoFile(pb)));
             return;
@@ -137,39 +484,308 @@ impl Counter {
             return;
         }
 
-        match std::fs::File::open(&pb) {
-            Ok(f) => self.count_reader(CountsName::File(pb), BufReader::new(f)),
-            Err(e) => self.file_counts.push(Err(Error::FileCount(pb, e))),
+        let mut f = match std::fs::File::open(&pb) {
+            Ok(f) => f,
+            Err(e) => {
+                self.file_counts.push(Err(Error::FileCount(pb, e)));
+                return;
+            }
+        };
+        if self.config.archive {
+            self.count_archive(CountsName::File(pb), f);
+            return;
+        }
+
+        // the cache only knows about regular files, which have a stable identity;
+        // --per-line has to read every file, so it skips lookups
+        let metadata = f.metadata().ok().filter(|m| m.is_file());
+        if let (Some(cache), Some(metadata)) = (&mut self.cache, &metadata)
+            && self.config.per_line.is_none()
+            && resume.is_none()
+            && let Some(counts) = cache.lookup(metadata)
+        {
+            self.apply_counts(CountsName::File(pb), counts);
+            return;
         }
+
+        // only regular files can be seeked into, be it after a restart or to
+        // the start of a --bytes-range; decompressed ones are read through
+        let range_start = match self.config.bytes_range {
+            Some(range) if !self.config.decompress => range.start,
+            _ => 0,
+        };
+        let start = match (resume, &metadata) {
+            (_, None) => None,
+            (Some(partial), Some(metadata)) if partial.offset > metadata.len() => {
+                let e = std::io::Error::other("file shrank since the checkpoint");
+                self.file_counts.push(Err(Error::FileCount(pb, e)));
+                return;
+            }
+            (resume, Some(_)) => {
+                let start = match resume {
+                    Some(partial) => Start {
+                        offset: partial.offset,
+                        progress: Some(partial.progress),
+                    },
+                    None => Start {
+                        offset: range_start,
+                        progress: None,
+                    },
+                };
+                if start.offset > 0
+                    && let Err(e) = f.seek(SeekFrom::Start(start.offset))
+                {
+                    self.file_counts.push(Err(Error::FileCount(pb, e)));
+                    return;
+                }
+                Some(start)
+            }
+        };
+        let (counts, result) = self.read_counts(&CountsName::File(pb.clone()), f, start);
+        match result {
+            Ok(()) => {
+                if let (Some(cache), Some(metadata)) = (&mut self.cache, &metadata) {
+                    cache.store(metadata, &counts);
+                }
+            }
+            Err(e) => self.file_counts.push(Err(Error::FileCount(pb.clone(), e))),
+        }
+        self.apply_counts(CountsName::File(pb), counts);
     }
 
     fn count_stdin(&mut self) {
-        self.count_reader(CountsName::StdIn, BufReader::new(std::io::stdin().lock()));
+        if !self.config.passthrough {
+            self.count_stdin_from(std::io::stdin().lock());
+            return;
+        }
+
+        let mut stdin = passthrough::Tee::new(std::io::stdin().lock(), std::io::stdout().lock());
+        self.count_stdin_from(&mut stdin);
+        // counting may stop early, but the pipeline still gets all of stdin
+        if let Err(e) = std::io::copy(&mut stdin, &mut std::io::sink()) {
+            self.file_counts.push(Err(Error::FileCount("-".into(), e)));
+        }
+        if let Some(e) = stdin.finish() {
+            self.file_counts.push(Err(Error::Passthrough(e)));
+        }
+    }
+
+    fn count_stdin_from(&mut self, mut stdin: impl Read) {
+        if self.config.archive {
+            // archives need to be seekable, so hold all of stdin in memory
+            let mut content = Vec::new();
+            match stdin.read_to_end(&mut content) {
+                Ok(_) => self.count_archive(CountsName::StdIn, std::io::Cursor::new(content)),
+                Err(e) => self.file_counts.push(Err(Error::FileCount("-".into(), e))),
+            }
+        } else {
+            self.count_reader(CountsName::StdIn, stdin);
+        }
+    }
+
+    fn count_archive(&mut self, name: CountsName, mut content: impl Read + Seek) {
+        let result = match archive::detect(&mut content) {
+            Ok(Some(archive::Kind::Tar)) => self.count_tar(name.path(), content),
+            Ok(Some(archive::Kind::Zip)) => self.count_zip(name.path(), content),
+            Ok(None) => {
+                self.count_reader(name, content);
+                return;
+            }
+            Err(e) => Err(e),
+        };
+
+        if let Err(e) = result {
+            self.file_counts.push(Err(Error::FileCount(name.path(), e)));
+        }
+    }
+
+    fn count_tar(&mut self, archive_path: PathBuf, content: impl Read) -> std::io::Result<()> {
+        let mut tar = tar::Archive::new(decompress::reader(BufReader::new(content))?);
+        for entry in tar.entries()? {
+            let entry = entry?;
+            // skip directories, symlinks, hard links and other specials
+            if entry.header().entry_type().is_file() {
+                let name = CountsName::Member(archive_path.clone(), entry.path()?.into());
+                self.count_reader(name, entry);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn count_zip(
+        &mut self,
+        archive_path: PathBuf,
+        content: impl Read + Seek,
+    ) -> std::io::Result<()> {
+        let mut zip = zip::ZipArchive::new(content).map_err(std::io::Error::other)?;
+        for i in 0..zip.len() {
+            let file = zip.by_index(i).map_err(std::io::Error::other)?;
+            if file.is_file() {
+                let name = CountsName::Member(archive_path.clone(), file.name().into());
+                self.count_reader(name, file);
+            }
+        }
+
+        Ok(())
     }
 
-    fn count_reader(&mut self, name: CountsName, content: impl BufRead) {
-        let counts = FileCounts::from_reader(content);
+    fn count_reader(&mut self, name: CountsName, content: impl Read) {
+        let (counts, result) = self.read_counts(&name, content, None);
 
+        // report the failure, but still show whatever was counted before it
+        if let Err(e) = result {
+            self.file_counts.push(Err(Error::FileCount(name.path(), e)));
+        }
         self.apply_counts(name, counts);
     }
 
+    /// `start` is only given for seekable inputs, whose progress
+    /// `--checkpoint` can save, and says where `content` has been seeked to.
+    fn read_counts(
+        &self,
+        name: &CountsName,
+        content: impl Read,
+        start: Option<Start>,
+    ) -> (FileCounts, std::io::Result<()>) {
+        let range = self.config.bytes_range.unwrap_or_default();
+        if self.config.decompress {
+            // offsets into the decompressed stream can't be seeked to
+            let raw = decompress::RawBytes::new(content);
+            let raw_bytes = raw.counter();
+            match decompress::reader(BufReader::new(raw)).and_then(|r| range.select(r, 0)) {
+                Ok(reader) => {
+                    let (mut counts, result) = self.count_records(name, reader, None);
+                    counts.compressed_bytes = raw_bytes.get();
+                    (counts, result)
+                }
+                Err(e) => (FileCounts::new(&self.config.counting), Err(e)),
+            }
+        } else {
+            let offset = start.as_ref().map_or(0, |start| start.offset);
+            match range.select(BufReader::new(content), offset) {
+                Ok(reader) => self.count_records(name, reader, start),
+                Err(e) => (FileCounts::new(&self.config.counting), Err(e)),
+            }
+        }
+    }
+
+    // With --per-line, records are reported as they're read rather than
+    // held until the end, so mwc works as a streaming line linter.
+    fn count_records(
+        &self,
+        name: &CountsName,
+        reader: impl BufRead,
+        start: Option<Start>,
+    ) -> (FileCounts, std::io::Result<()>) {
+        use std::io::Write;
+
+        let options = &self.config.counting;
+        let (offset, mut counter) = match start {
+            Some(Start {
+                offset,
+                progress: Some(progress),
+            }) => (Some(offset), RecordCounter::resume(options, progress)),
+            start => (start.map(|s| s.offset), RecordCounter::new(options)),
+        };
+        if self.config.bytes_range.is_some_and(|range| range.start > 0) {
+            counter = counter.past_start();
+        }
+        // only tracked when there's a checkpoint to save it in; skipped
+        // records move it along too
+        let base = offset
+            .filter(|_| self.checkpoint.is_some())
+            .map(|offset| offset - counter.consumed());
+        let name = name.path();
+        let name = name.display().to_string();
+        let mut stdout = self.config.per_line.map(|_| std::io::stdout().lock());
+        let result = counter.read_from(reader, |counter, record| {
+            if let (Some(format), Some(stdout)) = (self.config.per_line, &mut stdout)
+                && self.config.line_filter.iter().all(|c| c.matches(record))
+            {
+                // a closed stdout only loses the listing, counting carries on
+                let _ = match format {
+                    OutputFormat::Ndjson => report::Record::Line {
+                        name: &name,
+                        counts: record,
+                    }
+                    .write_line(stdout),
+                    _ => writeln!(stdout, "{}", record.describe(&name)),
+                };
+            }
+            if let Some(base) = base
+                && self
+                    .checkpoint
+                    .as_ref()
+                    .is_some_and(checkpoint::Writer::due)
+            {
+                self.save_checkpoint(Some(checkpoint::Partial {
+                    offset: base + counter.consumed(),
+                    progress: counter.progress(),
+                }));
+            }
+        });
+        (counter.finish(), result)
+    }
+
     fn apply_counts(&mut self, name: CountsName, counts: FileCounts) {
-        self.widths = self.widths.max((&counts).into());
+        if counts.unsplit_bytes > 0 {
+            eprintln!(
+                "mwc: {}: --tokens pattern failed on {} bytes, counted as a token each",
+                name.to_string().trim_start(),
+                counts.unsplit_bytes
+            );
+        }
+        self.counted += 1;
+        self.widths = self
+            .widths
+            .max(ColumnWidths::new(&counts, &self.config.numbers));
         self.totals += &counts;
         self.file_counts.push(Ok(CountedInput(name, counts)));
     }
 
     fn count_default(&mut self) {
-        self.apply_counts(CountsName::StdIn, FileCounts::default());
+        self.apply_counts(CountsName::StdIn, FileCounts::new(&self.config.counting));
     }
 }
 
+/// Where counting a seekable file starts: its beginning or that of the
+/// `--bytes-range`, or wherever `--resume` says a checkpoint left off.
+struct Start {
+    offset: u64,
+    progress: Option<Progress>,
+}
+
 struct CountsConfig {
     lines: bool,
     words: bool,
     bytes: bool,
     chars: bool,
     max_line_length: bool,
+    paragraphs: bool,
+    sentences: bool,
+    tokens: bool,
+    // patterns, record separator, --tokens vocabulary and the like
+    counting: CountOptions,
+    decompress: bool,
+    compressed_bytes: bool,
+    archive: bool,
+    // order of the built-in columns and the name; only shown columns are printed
+    layout: Vec<Field>,
+    numbers: NumberFormat,
+    // whether stdout gets escape codes, already resolved from --color
+    color: bool,
+    // --per-line output, as text or NDJSON, for records matching every --where
+    per_line: Option<OutputFormat>,
+    line_filter: Vec<per_line::Condition>,
+    // --posix, --gnu or POSIXLY_CORRECT
+    conformance: Conformance,
+    // copy stdin to stdout while counting it
+    passthrough: bool,
+    // --bytes-range, applied to each input
+    bytes_range: Option<ByteRange>,
+    // where the --tokens vocabulary was loaded from
+    vocabulary_file: Option<PathBuf>,
 }
 
 impl Default for CountsConfig {
@@ -180,6 +796,22 @@ impl Default for CountsConfig {
             bytes: true,
             chars: false,
             max_line_length: false,
+            paragraphs: false,
+            sentences: false,
+            tokens: false,
+            counting: CountOptions::default(),
+            decompress: false,
+            compressed_bytes: false,
+            archive: false,
+            layout: Field::DEFAULT_LAYOUT.to_vec(),
+            numbers: NumberFormat::Plain,
+            color: false,
+            per_line: None,
+            line_filter: Vec::new(),
+            conformance: Conformance::Mwc,
+            passthrough: false,
+            bytes_range: None,
+            vocabulary_file: None,
         }
     }
 }
@@ -192,38 +824,525 @@ impl CountsConfig {
             bytes: false,
             chars: false,
             max_line_length: false,
+            paragraphs: false,
+            sentences: false,
+            tokens: false,
+            counting: CountOptions::default(),
+            decompress: false,
+            compressed_bytes: false,
+            archive: false,
+            layout: Field::DEFAULT_LAYOUT.to_vec(),
+            numbers: NumberFormat::Plain,
+            color: false,
+            per_line: None,
+            line_filter: Vec::new(),
+            conformance: Conformance::Mwc,
+            passthrough: false,
+            bytes_range: None,
+            vocabulary_file: None,
         }
     }
 }
 
-impl From<&Opts> for CountsConfig {
-    fn from(opts: &Opts) -> Self {
+impl CountsConfig {
+    fn shows(&self, column: Column) -> bool {
+        match column {
+            Column::Lines => self.lines,
+            Column::Words => self.words,
+            Column::Bytes => self.bytes,
+            Column::Chars => self.chars,
+            Column::MaxLineLength => self.max_line_length,
+            Column::Paragraphs => self.paragraphs,
+            Column::Sentences => self.sentences,
+            Column::Tokens => self.tokens,
+        }
+    }
+
+    fn show(&mut self, column: Column) {
+        match column {
+            Column::Lines => self.lines = true,
+            Column::Words => self.words = true,
+            Column::Bytes => self.bytes = true,
+            Column::Chars => self.chars = true,
+            Column::MaxLineLength => self.max_line_length = true,
+            Column::Paragraphs => self.paragraphs = true,
+            Column::Sentences => self.sentences = true,
+            Column::Tokens => self.tokens = true,
+        }
+    }
+
+    /// `--header` labels for the pattern, line ending, byte profile, field
+    /// and compressed byte columns.
+    fn extra_labels(&self) -> Vec<String> {
+        let mut labels = self.patterns();
+        if self.counting.line_endings {
+            labels.extend(line_endings::LABELS.map(String::from));
+        }
+        if self.counting.bytes_profile {
+            labels.extend(bytes_profile::LABELS.map(String::from));
+        }
+        if self.counting.fields.is_some() {
+            labels.extend(fields::LABELS.map(String::from));
+        }
+        if self.compressed_bytes {
+            labels.push(COMPRESSED_LABEL.into());
+        }
+        if self.sentences {
+            labels.push(WORDS_PER_SENTENCE_LABEL.into());
+        }
+        labels
+    }
+
+    /// The `--count-matches` patterns as regexes, so `-F` ones come escaped.
+    fn patterns(&self) -> Vec<String> {
+        self.counting
+            .patterns
+            .iter()
+            .map(|pattern| pattern.as_str().to_string())
+            .collect()
+    }
+
+    /// The built-in columns that are shown, in output order.
+    fn columns(&self) -> Vec<Column> {
+        self.shown_columns(&self.layout)
+    }
+
+    fn shown_columns(&self, fields: &[Field]) -> Vec<Column> {
+        fields
+            .iter()
+            .filter_map(|field| match *field {
+                Field::Count(column) if self.shows(column) => Some(column),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Describes every setting that changes the counts themselves (as opposed
+    /// to which of them are shown), so cached counts are only reused when
+    /// they'd come out the same.
+    fn fingerprint(&self) -> String {
+        let counting = &self.counting;
+        let patterns: Vec<_> = counting.patterns.iter().map(Regex::as_str).collect();
+        let vocabulary = counting
+            .vocabulary
+            .as_ref()
+            .map(tokens::Vocabulary::fingerprint);
+        let words = counting.word_pattern.as_ref().map(Regex::as_str);
+        format!(
+            "patterns={:?} match_mode={:?} record_separator={:?} line_endings={} decompress={} \
+             prose={} tokens={:?} bytes_profile={} fields={:?} words={:?} skip_lines={} \
+             max_records={:?} bytes_range={:?}",
+            patterns,
+            counting.match_mode,
+            counting.record_separator,
+            counting.line_endings,
+            self.decompress,
+            counting.prose,
+            vocabulary,
+            counting.bytes_profile,
+            counting.fields,
+            words,
+            counting.skip_records,
+            counting.max_records,
+            self.bytes_range
+        )
+    }
+
+    /// Everything in effect, keyed like a config file, for `--print-config`.
+    fn settings(&self) -> toml::Table {
+        fn value_name(value: impl ValueEnum) -> String {
+            value
+                .to_possible_value()
+                .map_or_else(String::new, |v| v.get_name().to_string())
+        }
+        let counting = &self.counting;
+        let mut table = toml::Table::new();
+        let mut set = |key: &str, value: toml::Value| table.insert(key.into(), value);
+
+        let columns: Vec<&str> = self
+            .layout
+            .iter()
+            .filter_map(|field| match *field {
+                Field::Count(column) if self.shows(column) => Some(column.name()),
+                Field::Count(_) => None,
+                Field::Name => Some("name"),
+            })
+            .collect();
+        set("columns", columns.into());
+        if !counting.patterns.is_empty() {
+            let patterns: Vec<&str> = counting.patterns.iter().map(Regex::as_str).collect();
+            set("count_matches", patterns.into());
+            set("match_mode", value_name(counting.match_mode).into());
+        }
+        if let Some(ref pattern) = counting.word_pattern {
+            set("word_regex", pattern.as_str().into());
+        }
+        set(
+            "record_separator",
+            escape_separator(&counting.record_separator).into(),
+        );
+        set("line_endings", counting.line_endings.into());
+        set("bytes_profile", counting.bytes_profile.into());
+        if let Some(delimited) = counting.fields {
+            set("fields", escape_separator(&[delimited.delimiter]).into());
+            set("rfc4180", delimited.quoting.into());
+        }
+        set("skip_lines", (counting.skip_records as i64).into());
+        if let Some(max) = counting.max_records {
+            set("max_records", (max as i64).into());
+        }
+        if let Some(range) = self.bytes_range {
+            set("bytes_range", range.to_string().into());
+        }
+        if let Some(ref path) = self.vocabulary_file {
+            set("tokens", path.display().to_string().into());
+        }
+        set("decompress", self.decompress.into());
+        set("compressed_bytes", self.compressed_bytes.into());
+        set("archive", self.archive.into());
+        match self.numbers {
+            NumberFormat::Plain => {
+                set("human_readable", false.into());
+                set("grouping", false.into());
+            }
+            NumberFormat::Grouped(_) => {
+                set("grouping", true.into());
+            }
+            NumberFormat::Human(units) => {
+                set("human_readable", true.into());
+                set("units", value_name(units).into());
+            }
+        }
+        // as resolved for this run's output
+        let color = if self.color {
+            ColorChoice::Always
+        } else {
+            ColorChoice::Never
+        };
+        set("color", value_name(color).into());
+        set("per_line", self.per_line.is_some().into());
+        if !self.line_filter.is_empty() {
+            let conditions: Vec<String> = self.line_filter.iter().map(|c| c.to_string()).collect();
+            set("where", conditions.into());
+        }
+        set("posix", (self.conformance == Conformance::Posix).into());
+        set("gnu", (self.conformance == Conformance::Gnu).into());
+        set("passthrough", self.passthrough.into());
+        table
+    }
+}
+
+impl TryFrom<&Opts> for CountsConfig {
+    type Error = Error;
+
+    fn try_from(opts: &Opts) -> Result<Self, Self::Error> {
         // only set specific flags if _some_ option was passed
-        if opts.lines || opts.bytes || opts.chars || opts.words || opts.max_line_length {
+        let max_line_length = opts.max_line_length;
+        let mut out = if !opts.columns.is_empty() {
+            let mut out = Self::new();
+            for field in &opts.columns {
+                if let Field::Count(column) = *field {
+                    out.show(column);
+                }
+            }
+            out.layout = opts.columns.clone();
+            out
+        } else if opts.lines || opts.bytes || opts.chars || opts.words || max_line_length {
             let mut out = Self::new();
             out.lines = out.lines || opts.lines;
             out.bytes = out.bytes || opts.bytes;
             out.chars = out.chars || opts.chars;
             out.words = out.words || opts.words;
-            out.max_line_length = out.max_line_length || opts.max_line_length;
+            out.max_line_length = out.max_line_length || max_line_length;
             out
         } else {
             Self::default()
+        };
+
+        // pattern columns are added on top of whichever built-in columns are
+        // selected, and so are paragraphs and sentences
+        out.paragraphs = out.paragraphs || opts.paragraphs;
+        out.sentences = out.sentences || opts.sentences;
+        if opts.tokens.is_some() {
+            out.vocabulary_file = opts.tokens.clone();
+            out.tokens = true;
+        } else if out.tokens {
+            return Err(Error::NoVocabulary);
+        }
+        out.counting = CountOptions::builder()
+            .patterns(opts.count_matches.clone())
+            .fixed_strings(opts.fixed_strings)
+            .match_mode(opts.match_mode)
+            .word_chars(opts.word_chars.clone())
+            .word_regex(opts.word_regex.clone())
+            .null_data(opts.null_data)
+            .record_separator(opts.record_separator.as_ref().map(|s| s.0.clone()))
+            .line_endings(opts.line_endings)
+            .prose(out.paragraphs || out.sentences)
+            .tokens(opts.tokens.clone())
+            .bytes_profile(opts.bytes_profile)
+            .fields(opts.fields.is_some())
+            .delimiter(opts.fields.flatten())
+            .rfc4180(opts.rfc4180)
+            .skip_records(opts.skip_lines)
+            .max_records(opts.max_records)
+            .build()?;
+        // --columns may not mention the columns these flags add; they go
+        // after the last listed count
+        for column in [Column::Paragraphs, Column::Sentences, Column::Tokens] {
+            if out.shows(column) && !out.layout.contains(&Field::Count(column)) {
+                let at = out
+                    .layout
+                    .iter()
+                    .rposition(|field| matches!(field, Field::Count(_)))
+                    .map_or(0, |last| last + 1);
+                out.layout.insert(at, Field::Count(column));
+            }
+        }
+        out.bytes_range = opts.bytes_range;
+        out.decompress = opts.decompress;
+        out.compressed_bytes = opts.compressed_bytes;
+        out.archive = opts.archive;
+        if opts.human_readable {
+            out.numbers = NumberFormat::Human(opts.units);
+        } else if opts.grouping {
+            out.numbers = NumberFormat::Grouped(numbers::locale_separator().into());
+        }
+        out.color = if opts.report.is_some() {
+            // files are never terminals, so only --color=always colors them
+            opts.color == ColorChoice::Always
+        } else if opts.passthrough {
+            opts.color.enabled_for(&std::io::stderr())
+        } else {
+            opts.color.enabled_for(&std::io::stdout())
+        };
+        out.passthrough = opts.passthrough;
+        if opts.per_line {
+            if opts.format == OutputFormat::Json {
+                return Err(Error::PerLineJson);
+            }
+            out.per_line = Some(opts.format);
+        }
+        out.line_filter = opts.where_.clone();
+
+        out.conformance = Conformance::from_flags(opts.posix, opts.gnu);
+        if out.conformance == Conformance::Posix {
+            if max_line_length {
+                return Err(Error::NotPosix("-L/--max-line-length"));
+            }
+            if opts.files_from.is_some() {
+                return Err(Error::NotPosix("--files0-from"));
+            }
+            // POSIX's synopsis is wc [-c|-m] [-lw] [file...]
+            if opts.bytes && opts.chars {
+                return Err(Error::PosixBytesAndChars);
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+/// The built-in count columns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Column {
+    Lines,
+    Words,
+    Bytes,
+    Chars,
+    MaxLineLength,
+    Paragraphs,
+    Sentences,
+    Tokens,
+}
+
+impl Column {
+    const ALL: [Column; 8] = [
+        Column::Lines,
+        Column::Words,
+        Column::Bytes,
+        Column::Chars,
+        Column::MaxLineLength,
+        Column::Paragraphs,
+        Column::Sentences,
+        Column::Tokens,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Column::Lines => "lines",
+            Column::Words => "words",
+            Column::Bytes => "bytes",
+            Column::Chars => "chars",
+            Column::MaxLineLength => "max_line_length",
+            Column::Paragraphs => "paragraphs",
+            Column::Sentences => "sentences",
+            Column::Tokens => "tokens",
+        }
+    }
+
+    fn quantity(self) -> Quantity {
+        match self {
+            Column::Bytes => Quantity::Bytes,
+            _ => Quantity::Count,
+        }
+    }
+
+    fn count(self, counts: &FileCounts) -> usize {
+        match self {
+            Column::Lines => counts.lines,
+            Column::Words => counts.words,
+            Column::Bytes => counts.bytes,
+            Column::Chars => counts.chars,
+            Column::MaxLineLength => counts.max_line_length,
+            Column::Paragraphs => counts.paragraphs,
+            Column::Sentences => counts.sentences,
+            Column::Tokens => counts.tokens,
+        }
+    }
+}
+
+impl std::str::FromStr for Column {
+    type Err = String;
+
+    // accepts the JSON key spelling as well as the flag spelling (max-line-length)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Column::ALL
+            .into_iter()
+            .find(|column| column.name() == s.replace('-', "_"))
+            .ok_or_else(|| {
+                let names: Vec<_> = Column::ALL.map(Column::name).into();
+                format!(
+                    "unknown column '{}' (expected {} or name)",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// A position in the text output: one of the built-in counts or the input name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Count(Column),
+    Name,
+}
+
+impl Field {
+    const DEFAULT_LAYOUT: [Field; 9] = [
+        Field::Count(Column::Lines),
+        Field::Count(Column::Words),
+        Field::Count(Column::Bytes),
+        Field::Count(Column::Chars),
+        Field::Count(Column::MaxLineLength),
+        Field::Count(Column::Paragraphs),
+        Field::Count(Column::Sentences),
+        Field::Count(Column::Tokens),
+        Field::Name,
+    ];
+}
+
+fn parse_field(s: &str) -> Result<Field, String> {
+    match s {
+        "name" => Ok(Field::Name),
+        _ => s.parse().map(Field::Count),
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SortKey {
+    field: Field,
+    descending: bool,
+}
+
+impl SortKey {
+    fn compare(
+        &self,
+        (a_name, a): &(CountsName, FileCounts),
+        (b_name, b): &(CountsName, FileCounts),
+    ) -> std::cmp::Ordering {
+        let ordering = match self.field {
+            Field::Count(column) => column.count(a).cmp(&column.count(b)),
+            Field::Name => a_name.path().cmp(&b_name.path()),
+        };
+        if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
         }
     }
 }
 
+fn parse_sort_key(s: &str) -> Result<SortKey, String> {
+    let (field, descending) = match s.rsplit_once(':') {
+        Some((field, "desc")) => (field, true),
+        Some((field, "asc")) => (field, false),
+        Some((_, direction)) => {
+            return Err(format!(
+                "unknown sort direction '{}' (expected asc or desc)",
+                direction
+            ));
+        }
+        None => (s, false),
+    };
+    Ok(SortKey {
+        field: parse_field(field)?,
+        descending,
+    })
+}
+
+#[test]
+fn test_parse_sort_key() {
+    let key = parse_sort_key("max-line-length:desc").unwrap();
+    assert_eq!(key.field, Field::Count(Column::MaxLineLength));
+    assert!(key.descending);
+    let key = parse_sort_key("name").unwrap();
+    assert_eq!(key.field, Field::Name);
+    assert!(!key.descending);
+    assert!(parse_sort_key("bytes:sideways").is_err());
+    assert!(parse_sort_key("pages").is_err());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// wc-style aligned columns
+    Text,
+    /// A single JSON document, usable as a --compare baseline
+    Json,
+    /// One JSON object per line, tagged with a "type" of line, file or total
+    Ndjson,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 enum CountsName {
     File(PathBuf),
+    // a file inside an archive: (archive, path inside the archive)
+    Member(PathBuf, PathBuf),
     StdIn,
     Blank,
     Total,
 }
 
+impl CountsName {
+    fn path(&self) -> PathBuf {
+        match self {
+            CountsName::File(path_buf) => path_buf.clone(),
+            CountsName::Member(archive, member) => {
+                format!("{}:{}", archive.display(), member.display()).into()
+            }
+            CountsName::StdIn | CountsName::Blank => "-".into(),
+            CountsName::Total => "total".into(),
+        }
+    }
+}
+
 impl Display for CountsName {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             CountsName::File(path_buf) => write!(f, " {}", path_buf.display()),
+            CountsName::Member(archive, member) => {
+                write!(f, " {}:{}", archive.display(), member.display())
+            }
             CountsName::StdIn => write!(f, " -"),
             CountsName::Blank => write!(f, ""),
             CountsName::Total => write!(f, " total"),
@@ -232,7 +1351,8 @@ impl Display for CountsName {
 }
 
 #[derive(Parser)]
-#[command(version)]
+// -h is --human-readable, as in du and ls, so help is only available as --help
+#[command(version, disable_help_flag = true)]
 struct Opts {
     #[arg(
         value_parser=parse_input,
@@ -253,6 +1373,295 @@ struct Opts {
 
     #[arg(long = "files0-from", value_parser = parse_input)]
     files_from: Option<Input>,
+
+    #[arg(
+        short = 'e',
+        long = "count-matches",
+        value_name = "PATTERN",
+        help = "Add a column counting matches of PATTERN (repeatable)"
+    )]
+    count_matches: Vec<String>,
+    #[arg(
+        short = 'F',
+        long,
+        help = "Treat --count-matches patterns as fixed strings"
+    )]
+    fixed_strings: bool,
+    #[arg(long, value_enum, default_value_t = MatchMode::Lines)]
+    match_mode: MatchMode,
+
+    #[arg(
+        long,
+        value_name = "CLASS",
+        conflicts_with = "word_regex",
+        help = "Words are runs of CLASS characters, e.g. [:alnum:]_ or \\p{L}\\p{N}, instead of non-whitespace"
+    )]
+    word_chars: Option<String>,
+    #[arg(
+        long,
+        value_name = "RE",
+        help = "Words are the non-overlapping matches of RE, instead of runs of non-whitespace"
+    )]
+    word_regex: Option<String>,
+
+    #[arg(
+        short = 'z',
+        long,
+        help = "Records are terminated by NUL bytes instead of newlines"
+    )]
+    null_data: bool,
+    #[arg(
+        long,
+        value_name = "BYTES",
+        value_parser = parse_record_separator,
+        conflicts_with = "null_data",
+        help = "Records are terminated by BYTES (escapes like \\r\\n and \\x1e are allowed)"
+    )]
+    record_separator: Option<RecordSeparator>,
+
+    #[arg(
+        long,
+        help = "Add LF, CRLF, CR, unterminated, BOM, invalid UTF-8 and NUL columns"
+    )]
+    line_endings: bool,
+    #[arg(
+        long,
+        help = "Add entropy, printable share and text/binary columns, and print byte frequency tables"
+    )]
+    bytes_profile: bool,
+    #[arg(
+        long,
+        value_name = "DELIM",
+        num_args = 0..=1,
+        require_equals = true,
+        value_parser = parse_delimiter,
+        help = "Add columns with the min and max fields per record, and records unlike the header; DELIM defaults to ,"
+    )]
+    fields: Option<Option<u8>>,
+    #[arg(
+        long,
+        requires = "fields",
+        help = "Honor RFC 4180 quoting with --fields, so quoted delimiters and newlines don't split"
+    )]
+    rfc4180: bool,
+
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 0,
+        help = "Leave the first N records of each input out of the counts"
+    )]
+    skip_lines: usize,
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Stop counting each input after N records (past any --skip-lines)"
+    )]
+    max_records: Option<usize>,
+    #[arg(
+        long,
+        value_name = "START-END",
+        value_parser = parse_byte_range,
+        help = "Only count bytes START (from 0) up to END (excluded) of each input; either may be left out"
+    )]
+    bytes_range: Option<ByteRange>,
+
+    #[arg(
+        short = 'Z',
+        long,
+        help = "Count the decompressed content of gzip, zstd, xz and bzip2 inputs"
+    )]
+    decompress: bool,
+    #[arg(
+        long,
+        requires = "decompress",
+        help = "Add a column with the compressed byte count of each input"
+    )]
+    compressed_bytes: bool,
+
+    #[arg(
+        long,
+        help = "Count each regular file inside tar (optionally compressed) and zip inputs"
+    )]
+    archive: bool,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Reuse counts of unchanged files from the cache at PATH"
+    )]
+    cache: Option<PathBuf>,
+    #[arg(
+        long,
+        requires = "cache",
+        help = "Print cache hits, misses and stores to stderr"
+    )]
+    cache_stats: bool,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Save progress to FILE periodically, for --resume after an interruption"
+    )]
+    checkpoint: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        default_value_t = 60,
+        requires = "checkpoint",
+        help = "How often to save the --checkpoint"
+    )]
+    checkpoint_interval: u64,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Carry on from a --checkpoint FILE left by an interrupted run with the same inputs and options"
+    )]
+    resume: Option<PathBuf>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["inputs", "files_from", "per_line"],
+        help = "Copy stdin to stdout unchanged while counting it; the counts go to stderr or --report"
+    )]
+    passthrough: bool,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Write the counts to FILE instead of stdout"
+    )]
+    report: Option<PathBuf>,
+
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Fail if any input has more than N lines"
+    )]
+    max_lines: Option<usize>,
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Fail if any input has a line wider than N (as measured by -L)"
+    )]
+    max_width: Option<usize>,
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Fail if any input has more than N bytes"
+    )]
+    max_bytes: Option<usize>,
+    #[arg(
+        long,
+        value_name = "BASELINE",
+        conflicts_with = "format",
+        help = "Show per-input changes against a report saved with --format=json"
+    )]
+    compare: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        requires = "compare",
+        help = "Fail if any shown count grew by more than PERCENT since the baseline"
+    )]
+    max_growth: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "LIST",
+        value_delimiter = ',',
+        value_parser = parse_field,
+        conflicts_with_all = ["lines", "words", "bytes", "chars", "max_line_length"],
+        help = "Columns to show, in order: lines, words, bytes, chars, max_line_length and name"
+    )]
+    columns: Vec<Field>,
+    #[arg(
+        long,
+        value_name = "COLUMN[:desc]",
+        value_parser = parse_sort_key,
+        help = "Order rows by a column or name, ascending unless suffixed with :desc"
+    )]
+    sort: Option<SortKey>,
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Show only the first N rows after sorting; without --sort, the N largest by the first column"
+    )]
+    top: Option<usize>,
+
+    #[arg(
+        short = 'h',
+        long,
+        conflicts_with = "grouping",
+        help = "Print counts like 1.5K and 12M; byte columns use --units"
+    )]
+    human_readable: bool,
+    #[arg(long, value_enum, default_value_t = Units::Iec, requires = "human_readable")]
+    units: Units,
+    #[arg(long, help = "Separate thousands the way LC_NUMERIC does")]
+    grouping: bool,
+    #[arg(
+        long,
+        help = "Add a column counting paragraphs (runs of non-blank lines)"
+    )]
+    paragraphs: bool,
+    #[arg(
+        long,
+        help = "Add columns counting sentences and the average words per sentence"
+    )]
+    sentences: bool,
+
+    #[arg(
+        long,
+        value_name = "VOCAB_FILE",
+        help = "Add a column counting BPE tokens of a tiktoken or tokenizer.json vocabulary"
+    )]
+    tokens: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Also report words, bytes, chars and width of every line as it's read"
+    )]
+    per_line: bool,
+    #[arg(
+        long = "where",
+        value_name = "CONDITION",
+        value_parser = per_line::parse_condition,
+        requires = "per_line",
+        help = "Only report lines where e.g. width>120 (fields: line, words, bytes, chars, width); repeatable"
+    )]
+    where_: Vec<per_line::Condition>,
+
+    #[arg(long, help = "Print a row of column labels first")]
+    header: bool,
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+    #[arg(long, help = "Leave out input names, as for a single stdin input")]
+    no_filename: bool,
+    #[arg(
+        long,
+        conflicts_with = "gnu",
+        help = "Print \"%d %d %d %s\" lines and reject GNU-only options, as POSIX specifies; also set by POSIXLY_CORRECT"
+    )]
+    posix: bool,
+    #[arg(
+        long,
+        help = "Pad columns like GNU wc, to 7 digits when reading stdin or another non-regular file"
+    )]
+    gnu: bool,
+    #[arg(
+        long,
+        help = "Print the settings in effect, including defaults from config files and MWC_OPTIONS, then exit"
+    )]
+    print_config: bool,
+    #[arg(
+        long,
+        help = "Ignore MWC_OPTIONS and the mwc.toml and .mwc.toml config files"
+    )]
+    no_config: bool,
+    #[arg(long, action = clap::ArgAction::Help, help = "Print help")]
+    help: Option<bool>,
 }
 
 #[derive(Clone)]
@@ -264,6 +1673,14 @@ enum Input {
 type BoxedError = Box<dyn std::error::Error + Send + Sync>;
 
 impl Input {
+    /// How the input is named on the command line.
+    fn path(&self) -> PathBuf {
+        match self {
+            Input::File(path_buf) => path_buf.clone(),
+            Input::StdIn => "-".into(),
+        }
+    }
+
     fn reader(&self) -> Result<InputBufReader, BoxedError> {
         match self {
             Input::File(path_buf) => Ok(BufReader::new(InputReader::file(path_buf)?)),
@@ -298,75 +1715,200 @@ impl std::io::Read for InputReader {
     }
 }
 
-fn parse_input(s: &str) -> Result<Input, clap::Error> {
-    if s == "-" {
-        Ok(Input::StdIn)
+#[derive(Clone)]
+struct RecordSeparator(Vec<u8>);
+
+fn parse_record_separator(s: &str) -> Result<RecordSeparator, String> {
+    parse_separator(s).map(RecordSeparator)
+}
+
+// Accepts the escapes people type in shells: \n, \r, \t, \0, \\ and \xHH.
+fn parse_separator(s: &str) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    let mut bytes = s.bytes();
+    while let Some(b) = bytes.next() {
+        if b != b'\\' {
+            out.push(b);
+            continue;
+        }
+        match bytes.next() {
+            Some(b'n') => out.push(b'\n'),
+            Some(b'r') => out.push(b'\r'),
+            Some(b't') => out.push(b'\t'),
+            Some(b'0') => out.push(NUL),
+            Some(b'\\') => out.push(b'\\'),
+            Some(b'x') => {
+                let hex = [bytes.next(), bytes.next()];
+                let [Some(hi), Some(lo)] = hex else {
+                    return Err("\\x must be followed by two hex digits".into());
+                };
+                let hex = std::str::from_utf8(&[hi, lo])
+                    .ok()
+                    .and_then(|h| u8::from_str_radix(h, 16).ok())
+                    .ok_or("\\x must be followed by two hex digits")?;
+                out.push(hex);
+            }
+            Some(other) => return Err(format!("unknown escape '\\{}'", other as char)),
+            None => return Err("trailing '\\'".into()),
+        }
+    }
+
+    if out.is_empty() {
+        Err("record separator must not be empty".into())
     } else {
-        Ok(Input::File(s.into()))
+        Ok(out)
     }
 }
 
-#[derive(Default, Debug)]
-struct FileCounts {
-    lines: usize,
-    words: usize,
-    bytes: usize,
-    chars: usize,
-    max_line_length: usize,
+// The inverse of parse_separator
+fn escape_separator(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&b| match b {
+            b'\n' => "\\n".into(),
+            b'\r' => "\\r".into(),
+            b'\t' => "\\t".into(),
+            NUL => "\\0".into(),
+            b'\\' => "\\\\".into(),
+            b if b.is_ascii_graphic() || b == b' ' => (b as char).to_string(),
+            b => format!("\\x{:02x}", b),
+        })
+        .collect()
 }
 
-const NEWLINE: u8 = 0x0a;
+#[test]
+fn test_parse_separator() {
+    assert_eq!(parse_separator("\\r\\n"), Ok(vec![b'\r', b'\n']));
+    assert_eq!(parse_separator("\\x1e"), Ok(vec![0x1e]));
+    assert_eq!(parse_separator("--"), Ok(b"--".to_vec()));
+    assert_eq!(parse_separator("\\0"), Ok(vec![0]));
+    assert!(parse_separator("").is_err());
+    assert!(parse_separator("\\xZZ").is_err());
+    assert!(parse_separator("\\").is_err());
+    for separator in [&b"\r\n"[..], b"\x1e", b"a\\b", b"\0\xff"] {
+        assert_eq!(
+            parse_separator(&escape_separator(separator)).unwrap(),
+            separator
+        );
+    }
+}
 
-impl FileCounts {
-    // wc behavior: Always returns partial counts collected before stream ends.
-    // Does not report errors for EOF/broken pipes - only outputs whatever
-    // was successfully counted. File-level errors (permissions, not found)
-    // are handled separately at the file opening stage.
-    fn from_reader(mut reader: impl BufRead) -> Self {
-        let mut counts = Self::default();
-        let mut buf = Vec::new();
-        while let Ok(bytes_read) = reader.read_until(NEWLINE, &mut buf) {
-            if bytes_read == 0 {
-                break;
-            }
-            counts.bytes += bytes_read;
-            counts.lines += 1;
-            counts.words += buf
-                .split(u8::is_ascii_whitespace)
-                .filter(|s| !s.is_empty())
-                .count();
-            let s = std::str::from_utf8(&buf);
-            counts.chars += s.map(|s| s.chars().count()).unwrap_or(0);
-            counts.max_line_length = counts
-                .max_line_length
-                .max(s.map(|s| s.trim_end_matches('\n').width()).unwrap_or(0));
-            buf.clear();
+// A single byte, written like a --record-separator
+fn parse_delimiter(s: &str) -> Result<u8, String> {
+    match parse_separator(s)?.as_slice() {
+        &[delimiter] => Ok(delimiter),
+        _ => Err("the delimiter must be a single byte".into()),
+    }
+}
+
+/// The part of each input `--bytes-range` counts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct ByteRange {
+    start: u64,
+    // exclusive; None runs to the end
+    end: Option<u64>,
+}
+
+impl ByteRange {
+    /// Narrows `content`, which is `offset` bytes into an input, down to the
+    /// range, reading through whatever comes before its start.
+    fn select<R: Read>(&self, mut content: R, offset: u64) -> std::io::Result<std::io::Take<R>> {
+        if offset < self.start {
+            let skipped = self.start - offset;
+            std::io::copy(&mut (&mut content).take(skipped), &mut std::io::sink())?;
         }
+        let end = self.end.unwrap_or(u64::MAX);
+        Ok(content.take(end.saturating_sub(offset.max(self.start))))
+    }
+}
 
-        counts
+impl std::fmt::Display for ByteRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-", self.start)?;
+        match self.end {
+            Some(end) => write!(f, "{}", end),
+            None => Ok(()),
+        }
+    }
+}
+
+// START-END, START- or -END
+fn parse_byte_range(s: &str) -> Result<ByteRange, String> {
+    let (start, end) = s.split_once('-').ok_or("expected START-END")?;
+    let offset = |n: &str| {
+        n.parse::<u64>()
+            .map_err(|_| format!("'{}' is not a byte offset", n))
+    };
+    let range = ByteRange {
+        start: if start.is_empty() { 0 } else { offset(start)? },
+        end: if end.is_empty() {
+            None
+        } else {
+            Some(offset(end)?)
+        },
+    };
+    match range.end {
+        _ if start.is_empty() && end.is_empty() => Err("expected START-END".into()),
+        Some(end) if end < range.start => Err("END must not come before START".into()),
+        _ => Ok(range),
     }
 }
 
-impl AddAssign<&Self> for FileCounts {
-    fn add_assign(&mut self, rhs: &Self) {
-        self.lines += rhs.lines;
-        self.bytes += rhs.bytes;
-        self.words += rhs.words;
-        self.chars += rhs.chars;
-        self.max_line_length = self.max_line_length.max(rhs.max_line_length);
+#[test]
+fn test_parse_byte_range() {
+    let range = |start, end| Ok(ByteRange { start, end });
+    assert_eq!(parse_byte_range("10-20"), range(10, Some(20)));
+    assert_eq!(parse_byte_range("10-"), range(10, None));
+    assert_eq!(parse_byte_range("-20"), range(0, Some(20)));
+    assert_eq!(parse_byte_range("5-5"), range(5, Some(5)));
+    assert!(parse_byte_range("-").is_err());
+    assert!(parse_byte_range("20").is_err());
+    assert!(parse_byte_range("20-10").is_err());
+    assert!(parse_byte_range("a-b").is_err());
+}
+
+fn parse_input(s: &str) -> Result<Input, clap::Error> {
+    if s == "-" {
+        Ok(Input::StdIn)
+    } else {
+        Ok(Input::File(s.into()))
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 struct ColumnWidths {
     lines: usize,
     words: usize,
     bytes: usize,
     chars: usize,
     max_line_length: usize,
+    matches: Vec<usize>,
+    line_endings: [usize; line_endings::COLUMN_COUNT],
+    bytes_profile: [usize; bytes_profile::COLUMN_COUNT],
+    fields: [usize; fields::COLUMN_COUNT],
+    compressed_bytes: usize,
+    paragraphs: usize,
+    sentences: usize,
+    tokens: usize,
+    words_per_sentence: usize,
+    // only used to pad names that have columns after them
+    name: usize,
 }
 
 impl ColumnWidths {
+    fn get(&self, column: Column) -> usize {
+        match column {
+            Column::Lines => self.lines,
+            Column::Words => self.words,
+            Column::Bytes => self.bytes,
+            Column::Chars => self.chars,
+            Column::MaxLineLength => self.max_line_length,
+            Column::Paragraphs => self.paragraphs,
+            Column::Sentences => self.sentences,
+            Column::Tokens => self.tokens,
+        }
+    }
+
     fn max(&self, other: Self) -> Self {
         Self {
             lines: self.lines.max(other.lines),
@@ -374,6 +1916,24 @@ impl ColumnWidths {
             bytes: self.bytes.max(other.bytes),
             chars: self.chars.max(other.chars),
             max_line_length: self.max_line_length.max(other.max_line_length),
+            matches: (0..self.matches.len().max(other.matches.len()))
+                .map(|i| {
+                    let ours = self.matches.get(i).copied().unwrap_or(2);
+                    let theirs = other.matches.get(i).copied().unwrap_or(2);
+                    ours.max(theirs)
+                })
+                .collect(),
+            line_endings: std::array::from_fn(|i| self.line_endings[i].max(other.line_endings[i])),
+            bytes_profile: std::array::from_fn(|i| {
+                self.bytes_profile[i].max(other.bytes_profile[i])
+            }),
+            fields: std::array::from_fn(|i| self.fields[i].max(other.fields[i])),
+            compressed_bytes: self.compressed_bytes.max(other.compressed_bytes),
+            paragraphs: self.paragraphs.max(other.paragraphs),
+            sentences: self.sentences.max(other.sentences),
+            tokens: self.tokens.max(other.tokens),
+            words_per_sentence: self.words_per_sentence.max(other.words_per_sentence),
+            name: self.name.max(other.name),
         }
     }
 }
@@ -386,22 +1946,85 @@ impl Default for ColumnWidths {
             bytes: 2,
             chars: 2,
             max_line_length: 2,
+            matches: Vec::new(),
+            line_endings: [2; line_endings::COLUMN_COUNT],
+            bytes_profile: [2; bytes_profile::COLUMN_COUNT],
+            fields: [2; fields::COLUMN_COUNT],
+            compressed_bytes: 2,
+            paragraphs: 2,
+            sentences: 2,
+            tokens: 2,
+            words_per_sentence: 2,
+            name: 0,
         }
     }
 }
 
-impl From<&FileCounts> for ColumnWidths {
-    fn from(value: &FileCounts) -> Self {
+impl ColumnWidths {
+    /// Widths of the `--header` labels.
+    fn labels(config: &CountsConfig) -> Self {
+        let width = |label: &str| 2.max(label.width() + 1);
         Self {
-            lines: 2.max(digits(value.lines) + 1),
-            words: 2.max(digits(value.words) + 1),
-            bytes: 2.max(digits(value.bytes) + 1),
-            chars: 2.max(digits(value.chars) + 1),
-            max_line_length: 2.max(digits(value.max_line_length) + 1),
+            lines: width(Column::Lines.name()),
+            words: width(Column::Words.name()),
+            bytes: width(Column::Bytes.name()),
+            chars: width(Column::Chars.name()),
+            max_line_length: width(Column::MaxLineLength.name()),
+            matches: config
+                .counting
+                .patterns
+                .iter()
+                .map(|pattern| width(pattern.as_str()))
+                .collect(),
+            line_endings: line_endings::LABELS.map(width),
+            bytes_profile: bytes_profile::LABELS.map(width),
+            fields: fields::LABELS.map(width),
+            compressed_bytes: width(COMPRESSED_LABEL),
+            paragraphs: width(Column::Paragraphs.name()),
+            sentences: width(Column::Sentences.name()),
+            tokens: width(Column::Tokens.name()),
+            words_per_sentence: width(WORDS_PER_SENTENCE_LABEL),
+            name: 0,
+        }
+    }
+
+    /// Widths of `value`'s cells as `format` renders them.
+    fn new(value: &FileCounts, format: &NumberFormat) -> Self {
+        let width = |n, quantity| 2.max(format.width(n, quantity) + 1);
+        Self {
+            lines: width(value.lines, Quantity::Count),
+            words: width(value.words, Quantity::Count),
+            bytes: width(value.bytes, Quantity::Bytes),
+            chars: width(value.chars, Quantity::Count),
+            max_line_length: width(value.max_line_length, Quantity::Count),
+            matches: value
+                .matches
+                .iter()
+                .map(|&n| width(n, Quantity::Count))
+                .collect(),
+            line_endings: value.line_endings.cells().map(|cell| 2.max(cell.len() + 1)),
+            bytes_profile: value
+                .bytes_profile
+                .cells()
+                .map(|cell| 2.max(cell.len() + 1)),
+            fields: value.fields.cells().map(|cell| 2.max(cell.len() + 1)),
+            compressed_bytes: width(value.compressed_bytes, Quantity::Bytes),
+            paragraphs: width(value.paragraphs, Quantity::Count),
+            sentences: width(value.sentences, Quantity::Count),
+            tokens: width(value.tokens, Quantity::Count),
+            words_per_sentence: 2.max(words_per_sentence_cell(value).len() + 1),
+            name: 0,
         }
     }
 }
 
+// The average sentence length, or "-" without any sentences to average
+fn words_per_sentence_cell(counts: &FileCounts) -> String {
+    counts
+        .words_per_sentence()
+        .map_or_else(|| "-".to_string(), |average| format!("{:.1}", average))
+}
+
 // Find the number of digits in a usize
 fn digits(n: usize) -> usize {
     if n == 0 {
@@ -420,67 +2043,222 @@ fn test_digits() {
     assert_eq!(digits(101), 3);
 }
 
-struct Printer {
+const NAME_LABEL: &str = " name";
+const COMPRESSED_LABEL: &str = "compressed";
+const WORDS_PER_SENTENCE_LABEL: &str = "words/sentence";
+
+/// What a line of text output shows: an input's counts or the column labels.
+#[derive(Clone, Copy)]
+enum Row<'c> {
+    Counts(&'c FileCounts),
+    Labels,
+}
+
+struct Printer<'a> {
     widths: ColumnWidths,
-    display: CountsConfig,
+    display: &'a CountsConfig,
+    // with --posix and --gnu, every cell gets this width and a single space
+    // between, instead of `widths`
+    uniform_width: Option<usize>,
 }
 
-impl Printer {
-    fn print_counts<W: std::fmt::Write>(
+impl Printer<'_> {
+    fn print_counts<W: std::fmt::Write>(&self, writer: &mut W, row: Row) -> Result<(), BoxedError> {
+        self.print_columns(writer, &self.display.columns(), row)?;
+        self.print_extra_counts(writer, row)
+    }
+
+    fn print_columns<W: std::fmt::Write>(
         &self,
         writer: &mut W,
-        counts: &FileCounts,
+        columns: &[Column],
+        row: Row,
     ) -> Result<(), BoxedError> {
-        if self.display.lines {
-            write!(
-                writer,
-                "{:>width$}",
-                counts.lines,
-                width = self.widths.lines
-            )?;
-        }
-        if self.display.words {
-            write!(
-                writer,
-                "{:>width$}",
-                counts.words,
-                width = self.widths.words
-            )?;
-        }
-        if self.display.bytes {
-            write!(
-                writer,
-                "{:>width$}",
-                counts.bytes,
-                width = self.widths.bytes
-            )?;
-        }
-        if self.display.chars {
-            write!(
-                writer,
-                "{:>width$}",
-                counts.chars,
-                width = self.widths.chars
-            )?;
-        }
-        if self.display.max_line_length {
-            write!(
-                writer,
-                "{:>width$}",
-                counts.max_line_length,
-                width = self.widths.max_line_length
-            )?;
+        for &column in columns {
+            let cell = self.cell(column, row);
+            write!(writer, "{:>width$}", cell, width = self.widths.get(column))?;
         }
 
         Ok(())
     }
 
+    fn cell(&self, column: Column, row: Row) -> String {
+        match row {
+            Row::Counts(counts) => self
+                .display
+                .numbers
+                .format(column.count(counts), column.quantity()),
+            Row::Labels => column.name().to_string(),
+        }
+    }
+
+    // Pattern, line ending, byte profile, field and compressed byte columns,
+    // which always follow the last built-in column.
+    fn print_extra_counts<W: std::fmt::Write>(
+        &self,
+        writer: &mut W,
+        row: Row,
+    ) -> Result<(), BoxedError> {
+        let cells = self.extra_cells(row);
+        let widths = self
+            .display
+            .counting
+            .patterns
+            .iter()
+            .enumerate()
+            .map(|(i, _)| self.widths.matches.get(i).copied().unwrap_or(2))
+            .chain(
+                self.widths
+                    .line_endings
+                    .into_iter()
+                    .filter(|_| self.display.counting.line_endings),
+            )
+            .chain(
+                self.widths
+                    .bytes_profile
+                    .into_iter()
+                    .filter(|_| self.display.counting.bytes_profile),
+            )
+            .chain(
+                self.widths
+                    .fields
+                    .into_iter()
+                    .filter(|_| self.display.counting.fields.is_some()),
+            )
+            .chain(Some(self.widths.compressed_bytes).filter(|_| self.display.compressed_bytes))
+            .chain(Some(self.widths.words_per_sentence).filter(|_| self.display.sentences));
+        for (cell, width) in cells.iter().zip(widths) {
+            write!(writer, "{:>width$}", cell, width = width)?;
+        }
+
+        Ok(())
+    }
+
+    fn extra_cells(&self, row: Row) -> Vec<String> {
+        let mut cells = Vec::new();
+        match row {
+            Row::Counts(counts) => {
+                cells.extend(
+                    counts
+                        .matches
+                        .iter()
+                        .map(|&n| self.display.numbers.format(n, Quantity::Count)),
+                );
+                if self.display.counting.line_endings {
+                    cells.extend(counts.line_endings.cells());
+                }
+                if self.display.counting.bytes_profile {
+                    cells.extend(counts.bytes_profile.cells());
+                }
+                if self.display.counting.fields.is_some() {
+                    cells.extend(counts.fields.cells());
+                }
+                if self.display.compressed_bytes {
+                    cells.push(
+                        self.display
+                            .numbers
+                            .format(counts.compressed_bytes, Quantity::Bytes),
+                    );
+                }
+                if self.display.sentences {
+                    cells.push(words_per_sentence_cell(counts));
+                }
+            }
+            Row::Labels => cells = self.display.extra_labels(),
+        }
+        cells
+    }
+
     fn print_file<W: std::fmt::Write, D: Display>(
         &self,
         writer: &mut W,
         file: D,
+        padded: bool,
+        style: Option<Style>,
+    ) -> Result<(), BoxedError> {
+        let name = file.to_string();
+        let padding = if padded {
+            self.widths.name.saturating_sub(name.width())
+        } else {
+            0
+        };
+        let name = match style {
+            Some(style) => color::paint(&name, style, self.display.color),
+            None => name,
+        };
+        write!(writer, "{}{:padding$}", name, "").map_err(Into::into)
+    }
+
+    fn print_row<W: std::fmt::Write, D: Display>(
+        &self,
+        writer: &mut W,
+        file: D,
+        row: Row,
+        name_style: Option<Style>,
+    ) -> Result<(), BoxedError> {
+        if let Some(width) = self.uniform_width {
+            return self.print_uniform_row(writer, file, row, width, name_style);
+        }
+        let layout = &self.display.layout;
+        let Some(at) = layout.iter().position(|field| *field == Field::Name) else {
+            return self.print_counts(writer, row);
+        };
+
+        let before = self.display.shown_columns(&layout[..at]);
+        let after = self.display.shown_columns(&layout[at + 1..]);
+        self.print_columns(writer, &before, row)?;
+        if after.is_empty() {
+            self.print_extra_counts(writer, row)?;
+            self.print_file(writer, &file, false, name_style)?;
+        } else {
+            self.print_file(writer, &file, true, name_style)?;
+            self.print_columns(writer, &after, row)?;
+            self.print_extra_counts(writer, row)?;
+        }
+        Ok(())
+    }
+
+    fn print_uniform_row<W: std::fmt::Write, D: Display>(
+        &self,
+        writer: &mut W,
+        file: D,
+        row: Row,
+        width: usize,
+        name_style: Option<Style>,
     ) -> Result<(), BoxedError> {
-        write!(writer, "{}", file).map_err(Into::into)
+        let layout = &self.display.layout;
+        let at = layout
+            .iter()
+            .position(|field| *field == Field::Name)
+            .unwrap_or(layout.len());
+        let pad = |cell: String| format!("{:>width$}", cell, width = width);
+        let columns = |fields| {
+            self.display
+                .shown_columns(fields)
+                .into_iter()
+                .map(|column| pad(self.cell(column, row)))
+        };
+        let extras = self.extra_cells(row).into_iter().map(pad);
+
+        // names are displayed with a leading space of their own
+        let name = file.to_string();
+        let name = Some(name.trim_start())
+            .filter(|name| !name.is_empty())
+            .map(|name| match name_style {
+                Some(style) => color::paint(name, style, self.display.color),
+                None => name.to_string(),
+            });
+        let mut cells: Vec<String> = columns(&layout[..at]).collect();
+        let after: Vec<String> = columns(layout.get(at + 1..).unwrap_or_default()).collect();
+        if after.is_empty() {
+            cells.extend(extras);
+            cells.extend(name);
+        } else {
+            cells.extend(name);
+            cells.extend(after);
+            cells.extend(extras);
+        }
+        write!(writer, "{}", cells.join(" ")).map_err(Into::into)
     }
 
     fn print_result<W: std::fmt::Write, D: Display>(
@@ -489,20 +2267,69 @@ impl Printer {
         file: D,
         file_count: FileCounts,
     ) -> Result<(), BoxedError> {
-        self.print_counts(writer, &file_count)?;
-        self.print_file(writer, &file)?;
+        self.print_row(writer, file, Row::Counts(&file_count), Some(Style::Dim))?;
         writeln!(writer).map_err(Into::into)
     }
-}
 
-struct IoToFmt<W: std::io::Write>(W);
+    fn print_total<W: std::fmt::Write>(
+        &self,
+        writer: &mut W,
+        totals: &FileCounts,
+    ) -> Result<(), BoxedError> {
+        let mut line = String::new();
+        self.print_row(&mut line, CountsName::Total, Row::Counts(totals), None)?;
+        writeln!(
+            writer,
+            "{}",
+            color::paint(&line, Style::Bold, self.display.color)
+        )
+        .map_err(Into::into)
+    }
 
-impl IoToFmt<std::io::StdoutLock<'_>> {
-    fn stdout() -> Self {
-        Self(std::io::stdout().lock())
+    /// Labels every shown column; `names` is false when no row has a name.
+    fn print_header<W: std::fmt::Write>(
+        &self,
+        writer: &mut W,
+        names: bool,
+    ) -> Result<(), BoxedError> {
+        let label = if names { NAME_LABEL } else { "" };
+        self.print_row(writer, label, Row::Labels, None)?;
+        writeln!(writer).map_err(Into::into)
     }
 }
 
+/// The `--bytes-profile` frequency table of one input, after the counts:
+/// every byte value that occurs, its count and share, most frequent first.
+/// Empty inputs have nothing to tabulate.
+fn print_byte_table<W: std::fmt::Write>(
+    writer: &mut W,
+    path: &Path,
+    profile: &bytes_profile::ByteProfile,
+) -> Result<(), BoxedError> {
+    let table = profile.table();
+    if table.is_empty() {
+        return Ok(());
+    }
+    let total: usize = table.iter().map(|&(_, n)| n).sum();
+    let width = table.first().map_or(1, |&(_, n)| digits(n));
+    writeln!(writer, "\n{}:", path.display())?;
+    for (byte, n) in table {
+        let share = n as f64 / total as f64 * 100.0;
+        writeln!(
+            writer,
+            "{:>6} {:>width$} {:>6.2}%",
+            bytes_profile::describe(byte),
+            n,
+            share,
+            width = width
+        )?;
+    }
+
+    Ok(())
+}
+
+struct IoToFmt<W: std::io::Write>(W);
+
 impl<W: std::io::Write> std::fmt::Write for IoToFmt<W> {
     fn write_str(&mut self, s: &str) -> std::fmt::Result {
         self.0.write_all(s.as_bytes()).map_err(|_| std::fmt::Error)
@@ -513,18 +2340,28 @@ impl<W: std::io::Write> std::fmt::Write for IoToFmt<W> {
 fn test_output_format() {
     let mut output = String::new();
     Printer {
-        display: CountsConfig::default(),
+        display: &CountsConfig::default(),
         widths: ColumnWidths::default(),
+        uniform_width: None,
     }
     .print_counts(
         &mut output,
-        &FileCounts {
+        Row::Counts(&FileCounts {
             lines: 1,
             words: 1,
             bytes: 6,
             chars: 2,
             max_line_length: 1,
-        },
+            matches: Vec::new(),
+            line_endings: LineEndings::default(),
+            compressed_bytes: 0,
+            paragraphs: 0,
+            sentences: 0,
+            tokens: 0,
+            unsplit_bytes: 0,
+            bytes_profile: Default::default(),
+            fields: Default::default(),
+        }),
     )
     .unwrap();
     assert_eq!(" 1 1 6", output);
@@ -0,0 +1,160 @@
+use clap::ValueEnum;
+use unicode_width::UnicodeWidthStr;
+
+const COUNT_SUFFIXES: [&str; 6] = ["K", "M", "G", "T", "P", "E"];
+const IEC_SUFFIXES: [&str; 6] = ["Ki", "Mi", "Gi", "Ti", "Pi", "Ei"];
+const SI_SUFFIXES: [&str; 6] = ["k", "M", "G", "T", "P", "E"];
+
+/// What a number measures, which decides the `--human-readable` units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quantity {
+    Count,
+    Bytes,
+}
+
+/// Unit system for byte columns under `--human-readable`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Units {
+    /// Powers of 1024: Ki, Mi, Gi, ...
+    Iec,
+    /// Powers of 1000: k, M, G, ...
+    Si,
+}
+
+/// How counts are rendered in the text output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NumberFormat {
+    Plain,
+    /// Thousands separated by the given string
+    Grouped(String),
+    Human(Units),
+}
+
+impl NumberFormat {
+    pub fn format(&self, n: usize, quantity: Quantity) -> String {
+        match self {
+            NumberFormat::Plain => n.to_string(),
+            NumberFormat::Grouped(separator) => group(n, separator),
+            NumberFormat::Human(units) => match (quantity, units) {
+                (Quantity::Bytes, Units::Iec) => human(n, 1024, &IEC_SUFFIXES),
+                (Quantity::Bytes, Units::Si) => human(n, 1000, &SI_SUFFIXES),
+                (Quantity::Count, _) => human(n, 1000, &COUNT_SUFFIXES),
+            },
+        }
+    }
+
+    /// Display width of `n` once formatted.
+    pub fn width(&self, n: usize, quantity: Quantity) -> usize {
+        match self {
+            NumberFormat::Plain => crate::digits(n),
+            _ => self.format(n, quantity).width(),
+        }
+    }
+}
+
+fn group(n: usize, separator: &str) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push_str(separator);
+        }
+        out.push(digit);
+    }
+    out
+}
+
+// One decimal below 10 of a unit, whole units above, like `ls -h`.
+fn human(n: usize, base: usize, suffixes: &[&str]) -> String {
+    if n < base {
+        return n.to_string();
+    }
+
+    let mut value = n as f64 / base as f64;
+    let mut unit = 0;
+    while value >= base as f64 && unit + 1 < suffixes.len() {
+        value /= base as f64;
+        unit += 1;
+    }
+    let mut text = format_scaled(value);
+    // rounding can carry into the next unit, e.g. 1023.7Ki
+    if text.parse::<f64>().is_ok_and(|v| v >= base as f64) && unit + 1 < suffixes.len() {
+        unit += 1;
+        text = format_scaled(value / base as f64);
+    }
+    text + suffixes[unit]
+}
+
+fn format_scaled(value: f64) -> String {
+    if value < 9.95 {
+        format!("{:.1}", value)
+    } else {
+        format!("{:.0}", value)
+    }
+}
+
+/// The thousands separator for the locale in `LC_ALL`, `LC_NUMERIC` or
+/// `LANG`, whichever is set first.
+///
+/// This is a table of common locales rather than a `localeconv` call, so it
+/// works the same on systems without generated locales. Unknown locales use
+/// a comma, while `C` and `POSIX`, which is also what no locale at all means,
+/// don't group thousands.
+pub fn locale_separator() -> &'static str {
+    let locale = ["LC_ALL", "LC_NUMERIC", "LANG"]
+        .into_iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default();
+    separator_for(&locale)
+}
+
+fn separator_for(locale: &str) -> &'static str {
+    // drop the ".UTF-8" encoding and any "@euro" style modifier
+    let locale = locale.split(['.', '@']).next().unwrap_or_default();
+    let (language, territory) = locale.split_once('_').unwrap_or((locale, ""));
+    match (language, territory) {
+        ("" | "C" | "POSIX", _) => "",
+        ("de" | "fr" | "it" | "rm", "CH" | "LI") => "'",
+        ("de" | "es" | "it" | "nl" | "pt" | "da" | "id" | "tr" | "el" | "ro" | "sl" | "hr", _) => {
+            "."
+        }
+        (
+            "fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "nb" | "nn" | "fi" | "uk" | "hu" | "bg"
+            | "et" | "lt" | "lv",
+            _,
+        ) => "\u{202f}",
+        _ => ",",
+    }
+}
+
+#[test]
+fn test_number_format() {
+    assert_eq!(group(0, ","), "0");
+    assert_eq!(group(999, ","), "999");
+    assert_eq!(group(1000, ","), "1,000");
+    assert_eq!(group(1234567, "."), "1.234.567");
+
+    let human = NumberFormat::Human(Units::Iec);
+    assert_eq!(human.format(999, Quantity::Count), "999");
+    assert_eq!(human.format(1500, Quantity::Count), "1.5K");
+    assert_eq!(human.format(12_345_678, Quantity::Count), "12M");
+    assert_eq!(human.format(1536, Quantity::Bytes), "1.5Ki");
+    assert_eq!(human.format(1023, Quantity::Bytes), "1023");
+    assert_eq!(human.format(1024 * 1024 - 1, Quantity::Bytes), "1.0Mi");
+    assert_eq!(
+        NumberFormat::Human(Units::Si).format(1500, Quantity::Bytes),
+        "1.5k"
+    );
+    assert_eq!(human.width(1536, Quantity::Bytes), 5);
+
+    assert_eq!(separator_for("en_US.UTF-8"), ",");
+    assert_eq!(separator_for("de_DE.UTF-8"), ".");
+    assert_eq!(separator_for("de_CH"), "'");
+    assert_eq!(separator_for("fr_FR.UTF-8@euro"), "\u{202f}");
+    assert_eq!(separator_for("xx_XX"), ",");
+    assert_eq!(separator_for("C"), "");
+    assert_eq!(separator_for("C.UTF-8"), "");
+    assert_eq!(separator_for("POSIX"), "");
+    assert_eq!(separator_for(""), "");
+}
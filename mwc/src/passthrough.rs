@@ -0,0 +1,71 @@
+use std::io::{ErrorKind, Read, Write};
+
+/// Copies everything read from `inner` to `out`, like `tee` does, for
+/// `--passthrough`.
+///
+/// Reading carries on without copying once `out` fails, so the counts still
+/// cover the whole input even after the reader downstream went away.
+pub struct Tee<R, W> {
+    inner: R,
+    out: Option<W>,
+    error: Option<std::io::Error>,
+}
+
+impl<R: Read, W: Write> Tee<R, W> {
+    pub fn new(inner: R, out: W) -> Self {
+        Self {
+            inner,
+            out: Some(out),
+            error: None,
+        }
+    }
+
+    /// Why copying stopped early, unless it was because `out` was closed,
+    /// which is how pipelines like `mwc --passthrough | head` end.
+    pub fn finish(self) -> Option<std::io::Error> {
+        self.error
+    }
+}
+
+impl<R: Read, W: Write> Read for Tee<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if let Some(ref mut out) = self.out {
+            // flushed every time, so data moves along the pipeline as it arrives
+            if let Err(e) = out.write_all(&buf[..n]).and_then(|()| out.flush()) {
+                self.out = None;
+                if e.kind() != ErrorKind::BrokenPipe {
+                    self.error = Some(e);
+                }
+            }
+        }
+        Ok(n)
+    }
+}
+
+#[test]
+fn test_tee_outlives_closed_output() {
+    // takes 4 bytes, then acts like a pipe whose reader has gone
+    struct Closing<'a>(&'a mut Vec<u8>);
+    impl Write for Closing<'_> {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.0.len() >= 4 {
+                return Err(ErrorKind::BrokenPipe.into());
+            }
+            let n = buf.len().min(4 - self.0.len());
+            self.0.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut copied = Vec::new();
+    let mut tee = Tee::new(&b"hello world"[..], Closing(&mut copied));
+    let mut content = Vec::new();
+    tee.read_to_end(&mut content).unwrap();
+    assert!(tee.finish().is_none());
+    assert_eq!(content, b"hello world");
+    assert_eq!(copied, b"hell");
+}
@@ -0,0 +1,164 @@
+use std::fmt::Display;
+
+use serde::Serialize;
+
+/// Counts of a single record, reported by `--per-line`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct LineCounts {
+    /// 1-based record number
+    pub line: usize,
+    pub words: usize,
+    /// including the record separator
+    pub bytes: usize,
+    pub chars: usize,
+    /// display width, like `--max-line-length`
+    pub width: usize,
+}
+
+impl LineCounts {
+    fn get(&self, field: LineField) -> usize {
+        match field {
+            LineField::Line => self.line,
+            LineField::Words => self.words,
+            LineField::Bytes => self.bytes,
+            LineField::Chars => self.chars,
+            LineField::Width => self.width,
+        }
+    }
+
+    /// `name:line: words=.. bytes=.. chars=.. width=..`, so editors can jump to it.
+    pub fn describe(&self, name: impl Display) -> String {
+        format!(
+            "{}:{}: words={} bytes={} chars={} width={}",
+            name, self.line, self.words, self.bytes, self.chars, self.width
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineField {
+    Line,
+    Words,
+    Bytes,
+    Chars,
+    Width,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    NotEqual,
+    GreaterOrEqual,
+    Greater,
+}
+
+/// A `--where` filter such as `width>120`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Condition {
+    field: LineField,
+    comparison: Comparison,
+    value: usize,
+}
+
+impl Condition {
+    pub fn matches(&self, counts: &LineCounts) -> bool {
+        let actual = counts.get(self.field);
+        match self.comparison {
+            Comparison::Less => actual < self.value,
+            Comparison::LessOrEqual => actual <= self.value,
+            Comparison::Equal => actual == self.value,
+            Comparison::NotEqual => actual != self.value,
+            Comparison::GreaterOrEqual => actual >= self.value,
+            Comparison::Greater => actual > self.value,
+        }
+    }
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let field = match self.field {
+            LineField::Line => "line",
+            LineField::Words => "words",
+            LineField::Bytes => "bytes",
+            LineField::Chars => "chars",
+            LineField::Width => "width",
+        };
+        let operator = match self.comparison {
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Equal => "=",
+            Comparison::NotEqual => "!=",
+            Comparison::GreaterOrEqual => ">=",
+            Comparison::Greater => ">",
+        };
+        write!(f, "{}{}{}", field, operator, self.value)
+    }
+}
+
+// longer operators first, so ">=" isn't read as ">" followed by "=120"
+const OPERATORS: [(&str, Comparison); 7] = [
+    (">=", Comparison::GreaterOrEqual),
+    ("<=", Comparison::LessOrEqual),
+    ("==", Comparison::Equal),
+    ("!=", Comparison::NotEqual),
+    (">", Comparison::Greater),
+    ("<", Comparison::Less),
+    ("=", Comparison::Equal),
+];
+
+pub fn parse_condition(s: &str) -> Result<Condition, String> {
+    let (at, operator, comparison) = OPERATORS
+        .iter()
+        .filter_map(|&(operator, comparison)| Some((s.find(operator)?, operator, comparison)))
+        .min_by_key(|&(at, operator, _)| (at, std::cmp::Reverse(operator.len())))
+        .ok_or_else(|| format!("expected FIELD<OP>NUMBER, like width>120, not '{}'", s))?;
+
+    let field = match s[..at].trim() {
+        "line" => LineField::Line,
+        "words" => LineField::Words,
+        "bytes" => LineField::Bytes,
+        "chars" => LineField::Chars,
+        "width" => LineField::Width,
+        other => {
+            return Err(format!(
+                "unknown field '{}' (expected line, words, bytes, chars or width)",
+                other
+            ));
+        }
+    };
+    let value = s[at + operator.len()..]
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid number in '{}': {}", s, e))?;
+
+    Ok(Condition {
+        field,
+        comparison,
+        value,
+    })
+}
+
+#[test]
+fn test_parse_condition() {
+    let long = LineCounts {
+        line: 7,
+        words: 20,
+        bytes: 131,
+        chars: 130,
+        width: 130,
+    };
+    assert!(parse_condition("width>120").unwrap().matches(&long));
+    assert!(parse_condition("width >= 130").unwrap().matches(&long));
+    assert_eq!(
+        parse_condition("width >= 130").unwrap().to_string(),
+        "width>=130"
+    );
+    assert!(!parse_condition("width<=120").unwrap().matches(&long));
+    assert!(parse_condition("line=7").unwrap().matches(&long));
+    assert!(parse_condition("words!=0").unwrap().matches(&long));
+    assert!(parse_condition("height>1").is_err());
+    assert!(parse_condition("width>wide").is_err());
+    assert!(parse_condition("width").is_err());
+}
@@ -0,0 +1,119 @@
+// Closing punctuation allowed between a terminator and the following space,
+// as in `He left.)` or `"Why?"`.
+const CLOSERS: &[char] = &['"', '\'', ')', ']', '}', '»', '”', '’'];
+
+// UAX #29 ATerm: may end a sentence, unless it's part of an abbreviation.
+const A_TERMS: &[char] = &['.', '․', '﹒', '．'];
+
+// UAX #29 STerm (the common ones): always ends a sentence.
+const S_TERMS: &[char] = &[
+    '!', '?', '‼', '‽', '⁇', '⁈', '⁉', '。', '！', '？', '｡', '؟', '।', '॥',
+];
+
+// Lowercased, without the final period. Multi-period forms like "e.g." and
+// "U.S." are recognised by their inner period instead.
+const ABBREVIATIONS: &[&str] = &[
+    "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "vs", "cf", "al", "fig", "figs", "vol",
+    "approx", "inc", "ltd", "co", "corp", "dept", "est", "gen", "gov", "sen", "rep", "jan", "feb",
+    "mar", "apr", "jun", "jul", "aug", "sep", "sept", "oct", "nov", "dec",
+];
+
+/// Counts paragraphs and sentences a record at a time.
+///
+/// Paragraphs are runs of non-blank lines. Sentences end at a UAX #29
+/// terminator followed by whitespace, except that a period after a known
+/// abbreviation or a single-letter initial doesn't count, and neither does one
+/// followed by a lowercase word. A paragraph break always ends a sentence.
+#[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Scanner {
+    pub paragraphs: usize,
+    pub sentences: usize,
+    in_paragraph: bool,
+    // the current sentence has words that haven't been counted yet
+    pending: bool,
+    // the last word ended in a period; the next word decides
+    candidate: bool,
+}
+
+impl Scanner {
+    pub fn scan(&mut self, record: &[u8]) {
+        let text = String::from_utf8_lossy(record);
+        if text.trim().is_empty() {
+            self.end_paragraph();
+            return;
+        }
+        if !self.in_paragraph {
+            self.paragraphs += 1;
+            self.in_paragraph = true;
+        }
+
+        for word in text.split_whitespace() {
+            self.word(word);
+        }
+    }
+
+    pub fn finish(&mut self) {
+        self.end_paragraph();
+    }
+
+    fn end_paragraph(&mut self) {
+        if self.pending {
+            self.sentences += 1;
+        }
+        self.pending = false;
+        self.candidate = false;
+        self.in_paragraph = false;
+    }
+
+    fn word(&mut self, word: &str) {
+        if self.candidate {
+            self.candidate = false;
+            if !word.starts_with(char::is_lowercase) {
+                self.sentences += 1;
+                self.pending = false;
+            }
+        }
+        if word.contains(char::is_alphanumeric) {
+            self.pending = true;
+        }
+        if !self.pending {
+            return;
+        }
+
+        let word = word.trim_end_matches(CLOSERS);
+        if word.ends_with(S_TERMS) {
+            self.sentences += 1;
+            self.pending = false;
+        } else if let Some(stem) = word.strip_suffix(A_TERMS) {
+            self.candidate = !is_abbreviation(stem);
+        }
+    }
+}
+
+fn is_abbreviation(stem: &str) -> bool {
+    let stem = stem.trim_start_matches(|c: char| !c.is_alphanumeric());
+    let mut chars = stem.chars();
+    let initial = matches!((chars.next(), chars.next()), (Some(c), None) if c.is_alphabetic());
+    initial || stem.contains(A_TERMS) || ABBREVIATIONS.contains(&stem.to_lowercase().as_str())
+}
+
+#[test]
+fn test_scanner() {
+    let mut scanner = Scanner::default();
+    for line in [
+        "Dr. Smith met J. R. Tolkien at 3 p.m. on Jan. 3rd. They talked!\n",
+        "Did they? Yes... and then\n",
+        "more happened. \"Really.\" (It did.)\n",
+        "\n",
+        "  \n",
+        "An unterminated heading\n",
+        "Second paragraph, e.g. this one, continues.\n",
+    ] {
+        scanner.scan(line.as_bytes());
+    }
+    scanner.finish();
+    assert_eq!(scanner.paragraphs, 2);
+    // "3rd.", "talked!", "they?", "happened.", "Really." and "did.)", then the
+    // unterminated heading runs on until "continues."
+    assert_eq!(scanner.sentences, 7);
+}
@@ -0,0 +1,66 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{FileCounts, per_line::LineCounts};
+
+/// The `--format=json` output, which doubles as a `--compare` baseline.
+#[derive(Serialize, Deserialize)]
+pub struct Report {
+    /// The `--count-matches` patterns, in the order of each `matches` list
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub patterns: Vec<String>,
+    pub files: Vec<ReportEntry>,
+    pub total: FileCounts,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ReportEntry {
+    pub name: String,
+    #[serde(flatten)]
+    pub counts: FileCounts,
+}
+
+/// One line of `--format=ndjson` output.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Record<'a> {
+    Line {
+        name: &'a str,
+        #[serde(flatten)]
+        counts: &'a LineCounts,
+    },
+    // lines stand alone, so each names the patterns its matches are for
+    File {
+        name: &'a str,
+        #[serde(skip_serializing_if = "<[String]>::is_empty")]
+        patterns: &'a [String],
+        #[serde(flatten)]
+        counts: &'a FileCounts,
+    },
+    Total {
+        #[serde(skip_serializing_if = "<[String]>::is_empty")]
+        patterns: &'a [String],
+        #[serde(flatten)]
+        counts: &'a FileCounts,
+    },
+}
+
+impl Record<'_> {
+    pub fn write_line(&self, mut writer: impl std::io::Write) -> std::io::Result<()> {
+        serde_json::to_writer(&mut writer, self)?;
+        writeln!(writer)
+    }
+}
+
+impl Report {
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let file = std::fs::File::open(path)?;
+        Ok(serde_json::from_reader(std::io::BufReader::new(file))?)
+    }
+
+    pub fn write(&self, writer: impl std::io::Write) -> std::io::Result<()> {
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+}
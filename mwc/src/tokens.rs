@@ -0,0 +1,422 @@
+use std::{
+    collections::{HashMap, HashSet},
+    hash::{DefaultHasher, Hasher},
+    path::Path,
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use fancy_regex::Regex;
+use serde_json::Value;
+
+// `.tiktoken` files don't say which pre-tokenizer they go with, so it's
+// picked by vocabulary size: r50k/p50k, cl100k and o200k respectively.
+const GPT2_PATTERN: &str =
+    r"'s|'t|'re|'ve|'m|'ll|'d| ?\p{L}+| ?\p{N}+| ?[^\s\p{L}\p{N}]+|\s+(?!\S)|\s+";
+const CL100K_PATTERN: &str = r"'(?i:[sdmt]|ll|ve|re)|[^\r\n\p{L}\p{N}]?+\p{L}+|\p{N}{1,3}| ?[^\s\p{L}\p{N}]++[\r\n]*|\s*[\r\n]|\s+(?!\S)|\s+";
+const O200K_PATTERN: &str = concat!(
+    r"[^\r\n\p{L}\p{N}]?[\p{Lu}\p{Lt}\p{Lm}\p{Lo}\p{M}]*[\p{Ll}\p{Lm}\p{Lo}\p{M}]+(?i:'s|'t|'re|'ve|'m|'ll|'d)?",
+    r"|[^\r\n\p{L}\p{N}]?[\p{Lu}\p{Lt}\p{Lm}\p{Lo}\p{M}]+[\p{Ll}\p{Lm}\p{Lo}\p{M}]*(?i:'s|'t|'re|'ve|'m|'ll|'d)?",
+    r"|\p{N}{1,3}| ?[^\s\p{L}\p{N}]+[\r\n/]*|\s*[\r\n]+|\s+(?!\S)|\s+",
+);
+
+// Counted pieces are remembered, since prose repeats the same words a lot
+const CACHE_LIMIT: usize = 1 << 16;
+
+/// A byte-level BPE vocabulary, loaded from a tiktoken file or a Hugging Face
+/// `tokenizer.json`.
+///
+/// Only token counts are needed, so ids are never looked up: a piece of text
+/// is split into bytes and adjacent parts are merged, best-ranked first, until
+/// no known merge is left.
+pub struct Vocabulary {
+    pattern: Regex,
+    merges: Merges,
+    // tokenizer.json's ignore_merges: vocabulary entries are single tokens
+    whole: HashSet<Vec<u8>>,
+    add_prefix_space: bool,
+    digest: u64,
+}
+
+enum Merges {
+    /// tiktoken: a merge is ranked by the token it produces
+    Tokens(HashMap<Vec<u8>, u32>),
+    /// tokenizer.json: a merge is ranked by its pair, keyed by `pair_key`
+    Pairs(HashMap<Vec<u8>, u32>),
+}
+
+impl Vocabulary {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let mut hasher = DefaultHasher::new();
+        hasher.write(text.as_bytes());
+        let mut vocabulary = if text.trim_start().starts_with('{') {
+            Self::from_tokenizer_json(&text)?
+        } else {
+            Self::from_tiktoken(&text)?
+        };
+        vocabulary.digest = hasher.finish();
+        Ok(vocabulary)
+    }
+
+    /// Changes whenever the vocabulary file does, for the count cache.
+    pub fn fingerprint(&self) -> String {
+        format!("{:016x}", self.digest)
+    }
+
+    fn from_tiktoken(text: &str) -> Result<Self, String> {
+        let mut ranks = HashMap::new();
+        for (number, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let parsed = line.split_once(' ').and_then(|(token, rank)| {
+                Some((STANDARD.decode(token).ok()?, rank.trim().parse().ok()?))
+            });
+            let (token, rank) =
+                parsed.ok_or_else(|| format!("line {}: expected BASE64 RANK", number + 1))?;
+            ranks.insert(token, rank);
+        }
+        let pattern = match ranks.len() {
+            0 => return Err("no tokens".into()),
+            n if n >= 199_000 => O200K_PATTERN,
+            n if n >= 100_000 => CL100K_PATTERN,
+            _ => GPT2_PATTERN,
+        };
+
+        Ok(Self {
+            pattern: Regex::new(pattern).expect("built-in pattern is valid"),
+            whole: HashSet::new(),
+            merges: Merges::Tokens(ranks),
+            add_prefix_space: false,
+            digest: 0,
+        })
+    }
+
+    fn from_tokenizer_json(text: &str) -> Result<Self, String> {
+        let json: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        let model = &json["model"];
+        if model.get("type").is_some_and(|t| t != "BPE") {
+            return Err(format!("unsupported model type {}", model["type"]));
+        }
+
+        let bytes = unicode_to_byte();
+        let decode = |token: &str| -> Result<Vec<u8>, String> {
+            token
+                .chars()
+                .map(|c| bytes.get(&c).copied())
+                .collect::<Option<_>>()
+                .ok_or_else(|| format!("token '{}' isn't byte-level BPE", token))
+        };
+
+        let mut merges = HashMap::new();
+        let mut key = Vec::new();
+        let entries = model["merges"].as_array().ok_or("model has no merges")?;
+        for (rank, merge) in entries.iter().enumerate() {
+            // either "a b" or, in newer files, ["a", "b"]
+            let (a, b) = match merge {
+                Value::String(s) => s.split_once(' '),
+                Value::Array(pair) => pair
+                    .first()
+                    .and_then(Value::as_str)
+                    .zip(pair.get(1).and_then(Value::as_str)),
+                _ => None,
+            }
+            .ok_or_else(|| format!("malformed merge {}", merge))?;
+            pair_key(&decode(a)?, &decode(b)?, &mut key);
+            merges.entry(key.clone()).or_insert(rank as u32);
+        }
+
+        let whole = if model["ignore_merges"].as_bool().unwrap_or(false) {
+            let vocab = model["vocab"].as_object().ok_or("model has no vocab")?;
+            vocab
+                .keys()
+                .map(|token| decode(token))
+                .collect::<Result<_, _>>()?
+        } else {
+            HashSet::new()
+        };
+
+        let (pattern, add_prefix_space) = pre_tokenizer(&json["pre_tokenizer"])?;
+        Ok(Self {
+            pattern: Regex::new(&pattern).map_err(|e| e.to_string())?,
+            merges: Merges::Pairs(merges),
+            whole,
+            add_prefix_space,
+            digest: 0,
+        })
+    }
+
+    fn rank(&self, a: &[u8], b: &[u8], key: &mut Vec<u8>) -> Option<u32> {
+        match &self.merges {
+            Merges::Tokens(ranks) => {
+                key.clear();
+                key.extend_from_slice(a);
+                key.extend_from_slice(b);
+                ranks.get(key.as_slice()).copied()
+            }
+            Merges::Pairs(ranks) => {
+                pair_key(a, b, key);
+                ranks.get(key.as_slice()).copied()
+            }
+        }
+    }
+
+    fn is_token(&self, piece: &[u8]) -> bool {
+        match &self.merges {
+            Merges::Tokens(ranks) => ranks.contains_key(piece),
+            Merges::Pairs(_) => self.whole.contains(piece),
+        }
+    }
+
+    fn count_piece(&self, piece: &[u8]) -> usize {
+        if piece.len() < 2 || self.is_token(piece) {
+            return piece.len().min(1);
+        }
+
+        // parts are piece[bounds[i]..bounds[i + 1]]
+        let mut bounds: Vec<usize> = (0..=piece.len()).collect();
+        let mut key = Vec::new();
+        loop {
+            let best = bounds
+                .windows(3)
+                .enumerate()
+                .filter_map(|(i, w)| {
+                    let rank = self.rank(&piece[w[0]..w[1]], &piece[w[1]..w[2]], &mut key)?;
+                    Some((rank, i))
+                })
+                .min();
+            match best {
+                Some((_, i)) => {
+                    bounds.remove(i + 1);
+                }
+                None => return bounds.len() - 1,
+            }
+        }
+    }
+}
+
+// Length-prefixed so that ("a", "bc") and ("ab", "c") stay apart.
+fn pair_key(a: &[u8], b: &[u8], key: &mut Vec<u8>) {
+    key.clear();
+    key.extend_from_slice(&(a.len() as u32).to_le_bytes());
+    key.extend_from_slice(a);
+    key.extend_from_slice(b);
+}
+
+// The split pattern and prefix space setting of a ByteLevel pre-tokenizer,
+// either on its own (GPT-2 style) or after a regex Split (Llama 3 style).
+fn pre_tokenizer(config: &Value) -> Result<(String, bool), String> {
+    let steps = match config["type"].as_str() {
+        Some("Sequence") => config["pretokenizers"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default(),
+        _ => vec![config.clone()],
+    };
+    let byte_level = steps
+        .iter()
+        .find(|step| step["type"] == "ByteLevel")
+        .ok_or("only ByteLevel pre-tokenizers are supported")?;
+    let split = steps
+        .iter()
+        .find(|step| step["type"] == "Split")
+        .and_then(|step| step["pattern"]["Regex"].as_str());
+    let pattern = match split {
+        Some(pattern) => pattern,
+        None if byte_level["use_regex"].as_bool().unwrap_or(true) => GPT2_PATTERN,
+        None => return Err("ByteLevel without a split pattern isn't supported".into()),
+    };
+
+    Ok((
+        pattern.to_string(),
+        byte_level["add_prefix_space"].as_bool().unwrap_or(true),
+    ))
+}
+
+// GPT-2's reversible mapping of bytes to printable characters, inverted.
+fn unicode_to_byte() -> HashMap<char, u8> {
+    let printable = |b: u8| matches!(b, b'!'..=b'~' | 0xa1..=0xac | 0xae..=0xff);
+    let mut next = 256;
+    (0..=255u8)
+        .map(|b| {
+            if printable(b) {
+                (char::from(b), b)
+            } else {
+                let c = char::from_u32(next).expect("valid code point");
+                next += 1;
+                (c, b)
+            }
+        })
+        .collect()
+}
+
+/// Counts the tokens of a stream fed a record at a time.
+///
+/// A pre-token may continue into the next record (a run of blank lines, say),
+/// so the last one of every record is held back until more text arrives.
+pub struct TokenStream<'v> {
+    vocabulary: &'v Vocabulary,
+    pending: String,
+    started: bool,
+    cache: HashMap<String, usize>,
+    pub tokens: usize,
+    /// Bytes counted a token each, as the pattern gave up splitting them
+    /// (by hitting its backtracking limit, say)
+    pub unsplit: usize,
+}
+
+/// The state of a [`TokenStream`] between records, to carry on from later.
+#[derive(Default, Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TokenProgress {
+    pending: String,
+    started: bool,
+    tokens: usize,
+    #[serde(default)]
+    unsplit: usize,
+}
+
+impl<'v> TokenStream<'v> {
+    pub fn new(vocabulary: &'v Vocabulary) -> Self {
+        Self::resume(vocabulary, TokenProgress::default())
+    }
+
+    pub fn resume(vocabulary: &'v Vocabulary, progress: TokenProgress) -> Self {
+        Self {
+            vocabulary,
+            pending: progress.pending,
+            started: progress.started,
+            cache: HashMap::new(),
+            tokens: progress.tokens,
+            unsplit: progress.unsplit,
+        }
+    }
+
+    pub fn progress(&self) -> TokenProgress {
+        TokenProgress {
+            pending: self.pending.clone(),
+            started: self.started,
+            tokens: self.tokens,
+            unsplit: self.unsplit,
+        }
+    }
+
+    pub fn scan(&mut self, record: &[u8]) {
+        if !self.started {
+            self.started = true;
+            if self.vocabulary.add_prefix_space && !record.starts_with(b" ") {
+                self.pending.push(' ');
+            }
+        }
+        // pre-tokens don't reach across invalid UTF-8, whose bytes are a
+        // token each, as they'd be if the pattern gave up on them
+        for chunk in record.utf8_chunks() {
+            self.pending.push_str(chunk.valid());
+            if !chunk.invalid().is_empty() {
+                self.drain(true);
+                self.tokens += chunk.invalid().len();
+            }
+        }
+        self.drain(false);
+    }
+
+    pub fn finish(&mut self) {
+        self.drain(true);
+    }
+
+    fn drain(&mut self, last: bool) {
+        let text = std::mem::take(&mut self.pending);
+        let mut pieces = Vec::new();
+        let mut unsplit = None;
+        for found in self.vocabulary.pattern.find_iter(&text) {
+            match found {
+                Ok(m) => pieces.push((m.start(), m.end())),
+                Err(_) => {
+                    unsplit = Some(pieces.last().map_or(0, |&(_, end)| end));
+                    break;
+                }
+            }
+        }
+        let mut kept = text.len();
+        if let Some(start) = unsplit {
+            // nothing is held back, as the pattern would only fail on it again
+            self.tokens += text.len() - start;
+            self.unsplit += text.len() - start;
+        } else if !last && let Some((start, _)) = pieces.pop() {
+            kept = start;
+        }
+
+        for (start, end) in pieces {
+            self.tokens += self.count(&text[start..end]);
+        }
+        self.pending = text[kept..].to_string();
+    }
+
+    fn count(&mut self, piece: &str) -> usize {
+        if let Some(&count) = self.cache.get(piece) {
+            return count;
+        }
+        let count = self.vocabulary.count_piece(piece.as_bytes());
+        if self.cache.len() >= CACHE_LIMIT {
+            self.cache.clear();
+        }
+        self.cache.insert(piece.to_string(), count);
+        count
+    }
+}
+
+#[test]
+fn test_token_stream() {
+    let mut ranks: HashMap<Vec<u8>, u32> = (0..=255u8).map(|b| (vec![b], b as u32)).collect();
+    for (rank, token) in ["\n\n", " c", "at", " cat", "th", "the"].iter().enumerate() {
+        ranks.insert(token.as_bytes().to_vec(), 256 + rank as u32);
+    }
+    let vocabulary = Vocabulary {
+        pattern: Regex::new(CL100K_PATTERN).unwrap(),
+        whole: HashSet::new(),
+        merges: Merges::Tokens(ranks),
+        add_prefix_space: false,
+        digest: 0,
+    };
+
+    assert_eq!(vocabulary.count_piece(b"the"), 1);
+    assert_eq!(vocabulary.count_piece(b" cat"), 1);
+    assert_eq!(vocabulary.count_piece(b" dog"), 4);
+
+    // the blank line is a single "\n\n" token, even though it spans records
+    let mut stream = TokenStream::new(&vocabulary);
+    for record in ["the cat\n", "\n", "the dog"] {
+        stream.scan(record.as_bytes());
+    }
+    stream.finish();
+    assert_eq!(stream.tokens, 2 + 1 + 1 + 4);
+
+    // "the", the invalid byte and " cat", not a replacement character
+    let mut stream = TokenStream::new(&vocabulary);
+    stream.scan(b"the\xff cat");
+    stream.finish();
+    assert_eq!(stream.tokens, 3);
+}
+
+#[test]
+fn test_token_stream_unsplit() {
+    let mut ranks: HashMap<Vec<u8>, u32> = (0..=255u8).map(|b| (vec![b], b as u32)).collect();
+    ranks.insert(b"ab".to_vec(), 256);
+    // backtracking is only needed for the whitespace lookahead, after "ab"
+    let pattern = fancy_regex::RegexBuilder::new(CL100K_PATTERN)
+        .backtrack_limit(1)
+        .build()
+        .unwrap();
+    let vocabulary = Vocabulary {
+        pattern,
+        whole: HashSet::new(),
+        merges: Merges::Tokens(ranks),
+        add_prefix_space: false,
+        digest: 0,
+    };
+
+    let mut stream = TokenStream::new(&vocabulary);
+    stream.scan(b"ab   \n  cd");
+    stream.finish();
+    assert_eq!(stream.tokens, 1 + 8);
+    assert_eq!(stream.unsplit, 8);
+}
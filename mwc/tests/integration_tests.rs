@@ -625,3 +625,1703 @@ fn test_permission_denied() {
         );
     }
 }
+
+#[test]
+fn test_count_matches_lines() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let test_file = temp.child("test.txt");
+    test_file
+        .write_str("error: one\nok\nerror: two error\n")
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args(["-l", "-e", "error", test_file.path().to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^\s*3\s+2\s+.*test\.txt\n$").unwrap());
+}
+
+#[test]
+fn test_count_matches_occurrences() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let test_file = temp.child("test.txt");
+    test_file
+        .write_str("error: one\nok\nerror: two error\n")
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args([
+        "-l",
+        "-e",
+        "err(or)?",
+        "-e",
+        "o",
+        "--match-mode=occurrences",
+        test_file.path().to_str().unwrap(),
+    ])
+    .assert()
+    .success()
+    .stdout(predicate::str::is_match(r"^\s*3\s+3\s+6\s+.*test\.txt\n$").unwrap());
+}
+
+#[test]
+fn test_count_matches_fixed_strings() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let test_file = temp.child("test.txt");
+    test_file.write_str("a.b\naxb\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args(["-l", "-F", "-e", "a.b", test_file.path().to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^\s*2\s+1\s+.*test\.txt\n$").unwrap());
+}
+
+#[test]
+fn test_count_matches_totals() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let test_file1 = temp.child("test1.txt");
+    let test_file2 = temp.child("test2.txt");
+    test_file1.write_str("TODO\n").unwrap();
+    test_file2.write_str("TODO\nTODO later\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args([
+        "-l",
+        "-e",
+        "TODO",
+        test_file1.path().to_str().unwrap(),
+        test_file2.path().to_str().unwrap(),
+    ])
+    .assert()
+    .success()
+    .stdout(predicate::str::is_match(r"(?m)^\s*3\s+3\s+total\n$").unwrap());
+}
+
+#[test]
+fn test_count_matches_invalid_pattern() {
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args(["-e", "(unclosed"])
+        .write_stdin("hello\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid pattern"));
+}
+
+#[test]
+fn test_count_matches_named_in_json() {
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    let output = cmd
+        .args(["-e", "error", "-F", "-e", "a.b", "--format", "json"])
+        .write_stdin(
+            "error a.b
+error
+",
+        )
+        .output()
+        .unwrap();
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["patterns"], serde_json::json!(["error", "a\\.b"]));
+    assert_eq!(report["files"][0]["matches"], serde_json::json!([2, 1]));
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    let output = cmd
+        .args(["-e", "error", "--format", "ndjson"])
+        .write_stdin(
+            "error
+",
+        )
+        .output()
+        .unwrap();
+    for line in String::from_utf8(output.stdout).unwrap().lines() {
+        let record: serde_json::Value = serde_json::from_str(line).unwrap();
+        assert_eq!(record["patterns"], serde_json::json!(["error"]));
+    }
+}
+
+#[test]
+fn test_null_data_records() {
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args(["-l", "-L", "-z"])
+        .write_stdin("a/b\0c\0longer/path\0")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^\s*3\s+11\n$").unwrap());
+}
+
+#[test]
+fn test_record_separator_multibyte() {
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args(["-l", "-L", "--record-separator", r"\r\n"])
+        .write_stdin("one\r\ntwo\rthree\r\nfour")
+        .assert()
+        .success()
+        // "two\rthree" is a single record; the trailing "four" is unterminated
+        .stdout(predicate::str::is_match(r"^\s*3\s+\d+\n$").unwrap());
+}
+
+#[test]
+fn test_record_separator_hex_escape() {
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args(["-l", "-L", r"--record-separator=\x1e"])
+        .write_stdin("\x1e{\"a\":1}\n\x1e{\"bb\":22}\n")
+        .assert()
+        .success()
+        // the leading separator yields an empty first record; the trailing
+        // newline stays part of each record
+        .stdout(predicate::str::is_match(r"^\s*3\s+10\n$").unwrap());
+}
+
+#[test]
+fn test_record_separator_conflicts_with_null_data() {
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args(["-z", "--record-separator", ";"])
+        .write_stdin("a;b;")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_line_endings_report() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let unix = temp.child("unix.txt");
+    let dos = temp.child("dos.txt");
+    unix.write_str("one\ntwo\n").unwrap();
+    dos.write_binary(b"\xef\xbb\xbfone\r\ntwo\rthree").unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args([
+        "-l",
+        "--line-endings",
+        unix.path().to_str().unwrap(),
+        dos.path().to_str().unwrap(),
+    ])
+    .assert()
+    .success()
+    // lines, LF, CRLF, CR, unterminated, BOM, invalid UTF-8, NUL
+    .stdout(
+        predicate::str::is_match(r"(?m)^\s*2\s+2\s+0\s+0\s+0\s+none\s+0\s+0\s+.*unix\.txt$")
+            .unwrap(),
+    )
+    .stdout(
+        predicate::str::is_match(r"(?m)^\s*2\s+0\s+1\s+1\s+1\s+utf-8\s+0\s+0\s+.*dos\.txt$")
+            .unwrap(),
+    )
+    .stdout(
+        predicate::str::is_match(r"(?m)^\s*4\s+2\s+1\s+1\s+1\s+mixed\s+0\s+0\s+total$").unwrap(),
+    );
+}
+
+#[test]
+fn test_line_endings_invalid_utf8_and_nul() {
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args(["-c", "--line-endings"])
+        .write_stdin(&b"a\0b\xff\xfe\n\xc3\n"[..])
+        .assert()
+        .success()
+        // \xff and \xfe are separate invalid sequences, as is the truncated \xc3
+        .stdout(predicate::str::is_match(r"^\s*8\s+2\s+0\s+0\s+0\s+none\s+3\s+1\n$").unwrap());
+}
+
+#[test]
+fn test_line_endings_bom_at_file_start() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt")
+        .write_binary("\u{feff}head\nplain\n\u{feff}body\n".as_bytes())
+        .unwrap();
+
+    // the skipped header still holds the file's BOM
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["-l", "--line-endings", "--skip-lines", "1", "a.txt"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::is_match(r"^\s*2\s+2\s+0\s+0\s+0\s+utf-8\s+0\s+0\s+a\.txt\n$").unwrap(),
+        );
+
+    // a U+FEFF past the start isn't one
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["-l", "--line-endings", "--bytes-range", "14-", "a.txt"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::is_match(r"^\s*1\s+1\s+0\s+0\s+0\s+none\s+0\s+0\s+a\.txt\n$").unwrap(),
+        );
+}
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    use std::io::Write;
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[test]
+fn test_decompress_formats() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let content = b"hello world\nsecond line\n";
+    let fixtures = [
+        ("test.gz", gzip(content)),
+        ("test.zst", zstd::encode_all(&content[..], 0).unwrap()),
+        ("test.xz", {
+            let mut out = Vec::new();
+            std::io::Read::read_to_end(&mut xz2::read::XzEncoder::new(&content[..], 6), &mut out)
+                .unwrap();
+            out
+        }),
+        ("test.bz2", {
+            let mut out = Vec::new();
+            std::io::Read::read_to_end(
+                &mut bzip2::read::BzEncoder::new(&content[..], bzip2::Compression::default()),
+                &mut out,
+            )
+            .unwrap();
+            out
+        }),
+    ];
+
+    for (name, data) in fixtures {
+        let file = temp.child(name);
+        file.write_binary(&data).unwrap();
+
+        let mut cmd = Command::cargo_bin("mwc").unwrap();
+        cmd.args(["-Z", file.path().to_str().unwrap()])
+            .assert()
+            .success()
+            .stdout(predicate::str::is_match(r"^\s*2\s+4\s+24\s+.*test\.\w+\n$").unwrap());
+    }
+}
+
+#[test]
+fn test_decompress_concatenated_gzip_from_stdin() {
+    let mut data = gzip(b"one\n");
+    data.extend(gzip(b"two three\n"));
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.arg("-Z")
+        .write_stdin(data)
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^\s*2\s+3\s+14\n$").unwrap());
+}
+
+#[test]
+fn test_decompress_passes_plain_files_through() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let test_file = temp.child("test.txt");
+    test_file.write_str("plain text\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args(["-Z", test_file.path().to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^\s*1\s+2\s+11\s+.*test\.txt\n$").unwrap());
+}
+
+#[test]
+fn test_decompress_compressed_bytes_column() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let test_file = temp.child("test.gz");
+    let data = gzip(&b"x".repeat(10_000));
+    test_file.write_binary(&data).unwrap();
+
+    let expected = format!(r"^\s*10000\s+{}\s+.*test\.gz\n$", data.len());
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args([
+        "-c",
+        "-Z",
+        "--compressed-bytes",
+        test_file.path().to_str().unwrap(),
+    ])
+    .assert()
+    .success()
+    .stdout(predicate::str::is_match(expected).unwrap());
+}
+
+#[test]
+fn test_decompress_corrupt_stream_reports_partial_counts() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let test_file = temp.child("test.gz");
+    let content = (0..2000).map(|i| format!("line {i}\n")).collect::<String>();
+    let data = gzip(content.as_bytes());
+    test_file.write_binary(&data[..data.len() / 2]).unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args(["-l", "-Z", test_file.path().to_str().unwrap()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("test.gz: File read error"))
+        .stdout(predicate::str::is_match(r"^\s*\d+\s+.*test\.gz\n$").unwrap());
+}
+
+fn tar_fixture() -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::new());
+    let mut append = |path: &str, entry_type: tar::EntryType, data: &[u8]| {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(entry_type);
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        if entry_type == tar::EntryType::Symlink {
+            header.set_link_name("docs/a.txt").unwrap();
+        }
+        builder.append_data(&mut header, path, data).unwrap();
+    };
+    append("docs/", tar::EntryType::Directory, b"");
+    append("docs/a.txt", tar::EntryType::Regular, b"one two\n");
+    append("docs/link", tar::EntryType::Symlink, b"");
+    append(
+        "docs/b.txt",
+        tar::EntryType::Regular,
+        b"three\nfour five six\n",
+    );
+    builder.into_inner().unwrap()
+}
+
+#[test]
+fn test_archive_tar_members() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let archive = temp.child("drop.tar");
+    archive.write_binary(&tar_fixture()).unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args(["--archive", archive.path().to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(
+            predicate::function(|output: &str| output.lines().count() == 3)
+                .and(
+                    predicate::str::is_match(r"(?m)^\s*1\s+2\s+8\s+.*drop\.tar:docs/a\.txt$")
+                        .unwrap(),
+                )
+                .and(
+                    predicate::str::is_match(r"(?m)^\s*2\s+4\s+20\s+.*drop\.tar:docs/b\.txt$")
+                        .unwrap(),
+                )
+                .and(predicate::str::is_match(r"(?m)^\s*3\s+6\s+28\s+total$").unwrap()),
+        );
+}
+
+#[test]
+fn test_archive_compressed_tar_from_stdin() {
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args(["-l", "--archive"])
+        .write_stdin(gzip(&tar_fixture()))
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"(?m)^\s*1\s+-:docs/a\.txt$").unwrap())
+        .stdout(predicate::str::is_match(r"(?m)^\s*2\s+-:docs/b\.txt$").unwrap())
+        .stdout(predicate::str::is_match(r"(?m)^\s*3\s+total$").unwrap());
+}
+
+#[test]
+fn test_archive_zip_members() {
+    use std::io::Write;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let archive = temp.child("drop.zip");
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default();
+    zip.add_directory("data/", options).unwrap();
+    zip.start_file("data/x.txt", options).unwrap();
+    zip.write_all(b"alpha beta\n").unwrap();
+    zip.start_file("data/y.txt", options).unwrap();
+    zip.write_all(b"gamma\n").unwrap();
+    archive
+        .write_binary(&zip.finish().unwrap().into_inner())
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args(["-w", "--archive", archive.path().to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(
+            predicate::function(|output: &str| output.lines().count() == 3)
+                .and(predicate::str::is_match(r"(?m)^\s*2\s+.*drop\.zip:data/x\.txt$").unwrap())
+                .and(predicate::str::is_match(r"(?m)^\s*1\s+.*drop\.zip:data/y\.txt$").unwrap())
+                .and(predicate::str::is_match(r"(?m)^\s*3\s+total$").unwrap()),
+        );
+}
+
+#[test]
+fn test_archive_mode_counts_plain_inputs_whole() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let archive = temp.child("drop.tar");
+    let plain = temp.child("plain.txt");
+    archive.write_binary(&tar_fixture()).unwrap();
+    plain.write_str("just text\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args([
+        "--archive",
+        plain.path().to_str().unwrap(),
+        archive.path().to_str().unwrap(),
+    ])
+    .assert()
+    .success()
+    .stdout(predicate::str::is_match(r"(?m)^\s*1\s+2\s+10\s+.*plain\.txt$").unwrap())
+    .stdout(predicate::str::is_match(r"(?m)^\s*4\s+8\s+38\s+total$").unwrap());
+}
+
+// Files modified in the last second aren't cached, so backdate fixtures.
+fn backdate(path: &std::path::Path) {
+    let an_hour_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+    std::fs::File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(an_hour_ago)
+        .unwrap();
+}
+
+#[test]
+fn test_cache_reuses_counts_for_unchanged_files() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let test_file = temp.child("test.txt");
+    let cache = temp.child("counts.cache");
+    test_file.write_str("hello world\n").unwrap();
+    backdate(test_file.path());
+
+    let run = || {
+        let mut cmd = Command::cargo_bin("mwc").unwrap();
+        cmd.args([
+            "--cache",
+            cache.path().to_str().unwrap(),
+            "--cache-stats",
+            test_file.path().to_str().unwrap(),
+        ])
+        .assert()
+        .success()
+    };
+
+    run()
+        .stdout(predicate::str::is_match(r"^\s*1\s+2\s+12\s+.*test\.txt\n$").unwrap())
+        .stderr(predicate::str::contains("0 hits, 1 misses, 1 stored"));
+    run()
+        .stdout(predicate::str::is_match(r"^\s*1\s+2\s+12\s+.*test\.txt\n$").unwrap())
+        .stderr(predicate::str::contains("1 hits, 0 misses, 0 stored"));
+
+    // any change to the file invalidates its entry
+    test_file.write_str("hello again, world\n").unwrap();
+    backdate(test_file.path());
+    run()
+        .stdout(predicate::str::is_match(r"^\s*1\s+3\s+19\s+.*test\.txt\n$").unwrap())
+        .stderr(predicate::str::contains("0 hits, 1 misses, 1 stored"));
+}
+
+#[test]
+fn test_cache_replaces_corrupt_file() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let test_file = temp.child("test.txt");
+    let cache = temp.child("counts.cache");
+    test_file.write_str("hello world\n").unwrap();
+    backdate(test_file.path());
+    cache
+        .write_str("{\"version\": 2, \"configs\": {tru")
+        .unwrap();
+
+    let run = || {
+        let mut cmd = Command::cargo_bin("mwc").unwrap();
+        cmd.arg("--cache")
+            .arg(cache.path())
+            .arg("--cache-stats")
+            .arg(test_file.path())
+            .assert()
+            .success()
+    };
+    run().stderr(predicate::str::contains("0 hits, 1 misses, 1 stored"));
+    run().stderr(predicate::str::contains("1 hits, 0 misses, 0 stored"));
+}
+
+#[test]
+fn test_cache_is_keyed_by_config() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let test_file = temp.child("test.txt");
+    let cache = temp.child("counts.cache");
+    test_file.write_str("foo bar\nfoo\n").unwrap();
+    backdate(test_file.path());
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args([
+        "--cache",
+        cache.path().to_str().unwrap(),
+        test_file.path().to_str().unwrap(),
+    ])
+    .assert()
+    .success();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args([
+        "-l",
+        "-e",
+        "foo",
+        "--cache",
+        cache.path().to_str().unwrap(),
+        "--cache-stats",
+        test_file.path().to_str().unwrap(),
+    ])
+    .assert()
+    .success()
+    .stdout(predicate::str::is_match(r"^\s*2\s+2\s+.*test\.txt\n$").unwrap())
+    .stderr(predicate::str::contains("0 hits, 1 misses"));
+}
+
+#[test]
+fn test_cache_skips_recently_modified_files() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let test_file = temp.child("test.txt");
+    let cache = temp.child("counts.cache");
+    test_file.write_str("fresh\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args([
+        "--cache",
+        cache.path().to_str().unwrap(),
+        "--cache-stats",
+        test_file.path().to_str().unwrap(),
+    ])
+    .assert()
+    .success()
+    .stderr(predicate::str::contains("0 hits, 1 misses, 0 stored"));
+}
+
+#[test]
+fn test_cache_concurrent_writers() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let cache = temp.child("counts.cache");
+    let files: Vec<_> = (0..8)
+        .map(|i| {
+            let file = temp.child(format!("file{i}.txt"));
+            file.write_str(&"word ".repeat(i + 1)).unwrap();
+            backdate(file.path());
+            file
+        })
+        .collect();
+
+    let children: Vec<_> = files
+        .iter()
+        .map(|file| {
+            std::process::Command::new(assert_cmd::cargo::cargo_bin("mwc"))
+                .args(["--cache", cache.path().to_str().unwrap()])
+                .arg(file.path())
+                .stdout(std::process::Stdio::null())
+                .spawn()
+                .unwrap()
+        })
+        .collect();
+    for mut child in children {
+        assert!(child.wait().unwrap().success());
+    }
+
+    // every process's entries survived the others' writes
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args(["--cache", cache.path().to_str().unwrap(), "--cache-stats"])
+        .args(files.iter().map(|file| file.path()))
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("8 hits, 0 misses"));
+}
+
+#[test]
+fn test_max_lines_exceeded() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let short = temp.child("short.txt");
+    short.write_str("one\n").unwrap();
+    let long = temp.child("long.txt");
+    long.write_str("one\ntwo\nthree\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["-l", "--max-lines", "2", "short.txt", "long.txt"])
+        .assert()
+        .code(3)
+        .stdout(predicate::str::is_match(r"^ 1 short.txt\n 3 long.txt\n 4 total\n$").unwrap())
+        .stderr("mwc: long.txt: 3 lines exceeds --max-lines 2\n");
+}
+
+#[test]
+fn test_max_width() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child("test.txt");
+    file.write_str("short\na much longer line\n").unwrap();
+
+    // like --max-lines, the limit leaves the shown columns alone
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["--max-width", "80", "test.txt"])
+        .assert()
+        .success()
+        .stdout(" 2 5 25 test.txt\n");
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["-L", "--max-width", "10", "test.txt"])
+        .assert()
+        .code(3)
+        .stdout(" 18 test.txt\n")
+        .stderr(predicate::str::contains(
+            "18 max_line_length exceeds --max-width 10",
+        ));
+
+    // and isn't a GNU extension
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["--posix", "--max-width=80", "test.txt"])
+        .assert()
+        .success()
+        .stdout("2 5 25 test.txt\n");
+}
+
+#[test]
+fn test_json_format() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child("test.txt");
+    file.write_str("hello world\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    let output = cmd
+        .current_dir(temp.path())
+        .args(["--format", "json", "test.txt"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["files"][0]["name"], "test.txt");
+    assert_eq!(report["files"][0]["words"], 2);
+    assert_eq!(report["total"]["bytes"], 12);
+}
+
+#[test]
+fn test_compare_baseline() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let baseline = temp.child("baseline.json");
+    let file = temp.child("test.txt");
+    file.write_str("one\ntwo\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    let output = cmd
+        .current_dir(temp.path())
+        .args(["--format", "json", "test.txt"])
+        .output()
+        .unwrap();
+    baseline.write_binary(&output.stdout).unwrap();
+
+    file.write_str("one\ntwo\nthree\n").unwrap();
+    temp.child("added.txt").write_str("new\n").unwrap();
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["-l", "--compare", "baseline.json", "test.txt", "added.txt"])
+        .assert()
+        .success()
+        .stdout(
+            "test.txt: lines 2 -> 3 (+1, +50.0%)\n\
+             added.txt: new\n\
+             total: lines 2 -> 4 (+2, +100.0%)\n",
+        );
+}
+
+#[test]
+fn test_compare_max_growth() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let baseline = temp.child("baseline.json");
+    let file = temp.child("test.txt");
+    file.write_str("one\ntwo\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    let output = cmd
+        .current_dir(temp.path())
+        .args(["--format=json", "test.txt"])
+        .output()
+        .unwrap();
+    baseline.write_binary(&output.stdout).unwrap();
+
+    file.write_str("one\ntwo\nthree\n").unwrap();
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args([
+            "-l",
+            "--compare",
+            "baseline.json",
+            "--max-growth",
+            "60",
+            "test.txt",
+        ])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args([
+            "-l",
+            "--compare",
+            "baseline.json",
+            "--max-growth",
+            "25",
+            "test.txt",
+        ])
+        .assert()
+        .code(3)
+        .stdout(predicate::str::contains(
+            "test.txt: lines 2 -> 3 (+1, +50.0%) exceeds --max-growth",
+        ));
+}
+
+#[test]
+fn test_columns_order_and_name_position() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("one two\nthree\n").unwrap();
+    temp.child("longer.txt").write_str("x\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["--columns=name,bytes,lines", "a.txt", "longer.txt"])
+        .assert()
+        .success()
+        .stdout(
+            " a.txt      14 2\n\
+             \x20longer.txt  2 1\n\
+             \x20total      16 3\n",
+        );
+}
+
+#[test]
+fn test_columns_conflicts_with_column_flags() {
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args(["--columns=lines", "-w"])
+        .write_stdin("hi\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_sort_descending() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("small.txt").write_str("a\n").unwrap();
+    temp.child("big.txt").write_str("a\nb\nc\n").unwrap();
+    temp.child("mid.txt").write_str("a\nb\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["-l", "--sort=lines:desc", "small.txt", "big.txt", "mid.txt"])
+        .assert()
+        .success()
+        .stdout(" 3 big.txt\n 2 mid.txt\n 1 small.txt\n 6 total\n");
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["-l", "--sort=name", "small.txt", "big.txt", "mid.txt"])
+        .assert()
+        .success()
+        .stdout(" 3 big.txt\n 2 mid.txt\n 1 small.txt\n 6 total\n");
+}
+
+#[test]
+fn test_top_largest_files() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("small.txt").write_str("a\n").unwrap();
+    temp.child("big.txt").write_str("abcdefgh\n").unwrap();
+    temp.child("mid.txt").write_str("abcd\n").unwrap();
+
+    // the total still covers every input
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["-c", "--top", "2", "small.txt", "big.txt", "mid.txt"])
+        .assert()
+        .success()
+        .stdout(" 9 big.txt\n 5 mid.txt\n 16 total\n");
+}
+
+#[test]
+fn test_human_readable() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child("test.txt");
+    file.write_str(&"word\n".repeat(2000)).unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["-h", "test.txt"])
+        .assert()
+        .success()
+        .stdout(" 2.0K 2.0K 9.8Ki test.txt\n");
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["--human-readable", "--units=si", "-c", "test.txt"])
+        .assert()
+        .success()
+        .stdout(" 10k test.txt\n");
+}
+
+#[test]
+fn test_grouping_follows_locale() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("big.txt")
+        .write_str(&"x\n".repeat(1500))
+        .unwrap();
+    temp.child("small.txt").write_str("x\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .env("LC_ALL", "en_US.UTF-8")
+        .args(["-l", "--grouping", "big.txt", "small.txt"])
+        .assert()
+        .success()
+        .stdout(" 1,500 big.txt\n     1 small.txt\n 1,501 total\n");
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .env("LC_ALL", "de_DE.UTF-8")
+        .args(["-c", "--grouping", "big.txt"])
+        .assert()
+        .success()
+        .stdout(" 3.000 big.txt\n");
+
+    // the C locale doesn't group thousands
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .env("LC_ALL", "C")
+        .args(["-l", "--grouping", "big.txt"])
+        .assert()
+        .success()
+        .stdout(" 1500 big.txt\n");
+}
+
+#[test]
+fn test_short_h_is_not_help() {
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.arg("-h")
+        .write_stdin("hello\n")
+        .assert()
+        .success()
+        .stdout(" 1 1 6\n");
+}
+
+#[test]
+fn test_header() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("one two\n").unwrap();
+    temp.child("b.txt").write_str("three\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["--header", "a.txt", "b.txt"])
+        .assert()
+        .success()
+        .stdout(
+            " lines words bytes name\n     \
+             1     2     8 a.txt\n     \
+             1     1     6 b.txt\n     \
+             2     3    14 total\n",
+        );
+}
+
+#[test]
+fn test_header_without_names() {
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args(["--header", "-l"])
+        .write_stdin("hi\n")
+        .assert()
+        .success()
+        .stdout(" lines\n     1\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_header_to_full_stdout() {
+    let full = std::fs::OpenOptions::new()
+        .write(true)
+        .open("/dev/full")
+        .unwrap();
+    let output = std::process::Command::new(assert_cmd::cargo::cargo_bin("mwc"))
+        .args(["--header", "-l"])
+        .stdin(std::process::Stdio::null())
+        .stdout(full)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("panicked"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_rows_to_full_stdout() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("one two\n").unwrap();
+    temp.child("b.txt").write_str("three\n").unwrap();
+
+    // a result row, then results and a total, colored or not
+    for args in [vec!["a.txt"], vec!["--color=always", "a.txt", "b.txt"]] {
+        let full = std::fs::OpenOptions::new()
+            .write(true)
+            .open("/dev/full")
+            .unwrap();
+        let output = std::process::Command::new(assert_cmd::cargo::cargo_bin("mwc"))
+            .current_dir(temp.path())
+            .args(args)
+            .stdout(full)
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        assert!(!String::from_utf8_lossy(&output.stderr).contains("panicked"));
+    }
+}
+
+#[test]
+fn test_no_filename() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("one two\n").unwrap();
+    temp.child("b.txt").write_str("three\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["-w", "--no-filename", "a.txt", "b.txt"])
+        .assert()
+        .success()
+        .stdout(" 2\n 1\n 3 total\n");
+}
+
+#[test]
+fn test_color() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("one\n").unwrap();
+    temp.child("b.txt").write_str("two\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["-l", "--color=always", "a.txt", "b.txt", "missing.txt"])
+        .assert()
+        .failure()
+        .stdout(" 1\x1b[2m a.txt\x1b[0m\n 1\x1b[2m b.txt\x1b[0m\n\x1b[1m 2 total\x1b[0m\n")
+        .stderr("\x1b[31mmwc: missing.txt: No such file or directory\x1b[0m\n");
+
+    // not a terminal, so auto means no color even without NO_COLOR
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .env_remove("NO_COLOR")
+        .args(["-l", "a.txt"])
+        .assert()
+        .success()
+        .stdout(" 1 a.txt\n");
+}
+
+#[test]
+fn test_paragraphs_and_sentences() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child("doc.txt");
+    file.write_str(
+        "Mr. Smith went to Washington. He stayed\n\
+         for a week!\n\
+         \n\
+         \n\
+         Was it worth it? Perhaps. The U.S. capital is busy.\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["-w", "--paragraphs", "--sentences", "doc.txt"])
+        .assert()
+        .success()
+        .stdout(" 20 2 5 4.0 doc.txt\n");
+}
+
+#[test]
+fn test_sentences_without_any() {
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args(["-l", "--sentences"])
+        .write_stdin("")
+        .assert()
+        .success()
+        .stdout(" 0 0 -\n");
+}
+
+#[test]
+fn test_sort_by_sentences() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("One. Two.\n").unwrap();
+    temp.child("b.txt").write_str("One. Two. Three.\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args([
+            "--columns=sentences,name",
+            "--sort=sentences:desc",
+            "a.txt",
+            "b.txt",
+        ])
+        .assert()
+        .success()
+        .stdout(" 3 1.0 b.txt\n 2 1.0 a.txt\n 5 1.0 total\n");
+}
+
+const TIKTOKEN_VOCAB: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/tiny.tiktoken");
+const TOKENIZER_JSON: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/tiny_tokenizer.json"
+);
+
+#[test]
+fn test_tokens_tiktoken() {
+    // "the", " cat" and " sat" are single tokens; " dog" has no merges
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args(["-w", "--tokens", TIKTOKEN_VOCAB])
+        .write_stdin("the cat sat\n\nthe dog\n")
+        .assert()
+        .success()
+        .stdout(" 5 11\n");
+}
+
+#[test]
+fn test_tokens_tokenizer_json() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("cats.txt").write_str("the cat sat").unwrap();
+    temp.child("dogs.txt").write_str("the dog sat").unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["--columns=tokens,name", "--tokens", TOKENIZER_JSON])
+        .args(["cats.txt", "dogs.txt"])
+        .assert()
+        .success()
+        .stdout(" 3 cats.txt\n 6 dogs.txt\n 9 total\n");
+}
+
+#[test]
+fn test_tokens_invalid_vocabulary() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let vocab = temp.child("broken.tiktoken");
+    vocab.write_str("not base64 at all\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args(["--tokens", vocab.path().to_str().unwrap()])
+        .write_stdin("hello\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid vocabulary"))
+        .stderr(predicate::str::contains("line 1: expected BASE64 RANK"));
+}
+
+#[test]
+fn test_per_line_where() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("lines.txt")
+        .write_str("short\na much longer line here\n\nxx yy\n")
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["--per-line", "--where", "width>5", "--where=words>=2"])
+        .arg("lines.txt")
+        .assert()
+        .success()
+        .stdout("lines.txt:2: words=5 bytes=24 chars=24 width=23\n 4 8 37 lines.txt\n");
+}
+
+#[test]
+fn test_per_line_ndjson() {
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    let output = cmd
+        .args(["--per-line", "--format=ndjson"])
+        .write_stdin("héllo\n\nwide 世界\n")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let records: Vec<serde_json::Value> = output
+        .stdout
+        .split(|&b| b == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| serde_json::from_slice(line).unwrap())
+        .collect();
+    let types: Vec<_> = records
+        .iter()
+        .map(|r| r["type"].as_str().unwrap())
+        .collect();
+    assert_eq!(types, ["line", "line", "line", "file", "total"]);
+    assert_eq!(records[0]["bytes"], 7);
+    assert_eq!(records[0]["chars"], 6);
+    assert_eq!(records[2]["line"], 3);
+    assert_eq!(records[2]["width"], 9);
+    assert_eq!(records[3]["lines"], 3);
+    assert_eq!(records[4]["words"], 3);
+}
+
+#[test]
+fn test_per_line_rejects_json() {
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args(["--per-line", "--format=json"])
+        .write_stdin("a\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("use --format=ndjson"));
+}
+
+#[test]
+fn test_posix_format() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("hello world\nfoo\n").unwrap();
+    temp.child("b.txt").write_str("x\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["--posix", "a.txt", "b.txt"])
+        .assert()
+        .success()
+        .stdout("2 3 16 a.txt\n1 1 2 b.txt\n3 4 18 total\n");
+}
+
+#[test]
+fn test_posixly_correct_stdin() {
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.env("POSIXLY_CORRECT", "1")
+        .write_stdin("hi\n")
+        .assert()
+        .success()
+        // no file operand, so no name either
+        .stdout("1 1 3\n");
+}
+
+#[test]
+fn test_posix_rejects_gnu_options() {
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.env("POSIXLY_CORRECT", "1")
+        .arg("-L")
+        .write_stdin("hi\n")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "-L/--max-line-length is a GNU extension",
+        ));
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let list = temp.child("files0");
+    list.write_str("a.txt\0").unwrap();
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args(["--posix", "--files0-from", list.path().to_str().unwrap()])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("--files0-from is a GNU extension"));
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args(["--posix", "-c", "-m"])
+        .write_stdin("hi\n")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("-c and -m can't be combined"));
+}
+
+#[test]
+fn test_gnu_overrides_posixly_correct() {
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.env("POSIXLY_CORRECT", "1")
+        .args(["--gnu", "-L"])
+        .write_stdin("hi\n")
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_gnu_files_only_width() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("hello world\nfoo\n").unwrap();
+    temp.child("b.txt").write_str("x\n").unwrap();
+
+    // every column is as wide as the 18 bytes the files hold together
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["--gnu", "a.txt", "b.txt"])
+        .assert()
+        .success()
+        .stdout(" 2  3 16 a.txt\n 1  1  2 b.txt\n 3  4 18 total\n");
+}
+
+#[test]
+fn test_gnu_wide_columns_with_stdin() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("hello world\nfoo\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["--gnu", "a.txt", "-"])
+        .write_stdin("hi\n")
+        .assert()
+        .success()
+        .stdout(
+            "      2       3      16 a.txt\n      1       1       3 -\n      3       4      19 total\n",
+        );
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.arg("--gnu")
+        .write_stdin("hi\n")
+        .assert()
+        .success()
+        .stdout("      1       1       3\n");
+}
+
+#[test]
+fn test_gnu_single_column_unpadded() {
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args(["--gnu", "-l"])
+        .write_stdin("hi\n")
+        .assert()
+        .success()
+        .stdout("1\n");
+}
+
+#[test]
+fn test_bytes_profile_text() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("aab\n").unwrap();
+    temp.child("b.bin").write_binary(b"\x00\x01ab").unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["--bytes-profile", "-c", "a.txt", "b.bin"])
+        .assert()
+        .success()
+        .stdout(concat!(
+            " 4 1.500 100.0%   text a.txt\n",
+            " 4 2.000  50.0% binary b.bin\n",
+            " 8 2.156  75.0% binary total\n",
+            "\n",
+            "a.txt:\n",
+            "     a 2  50.00%\n",
+            // ties are in byte order
+            "    \\n 1  25.00%\n",
+            "     b 1  25.00%\n",
+            "\n",
+            "b.bin:\n",
+            "    \\0 1  25.00%\n",
+            "  \\x01 1  25.00%\n",
+            "     a 1  25.00%\n",
+            "     b 1  25.00%\n",
+        ));
+}
+
+#[test]
+fn test_bytes_profile_json() {
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    let output = cmd
+        .args(["--bytes-profile", "--format", "json"])
+        .write_stdin("zzzz")
+        .output()
+        .unwrap();
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let profile = &report["files"][0]["bytes_profile"];
+
+    assert_eq!(profile["entropy"], 0.0);
+    assert_eq!(profile["printable_ratio"], 1.0);
+    assert_eq!(profile["verdict"], "text");
+    assert_eq!(profile["frequencies"].as_array().unwrap().len(), 256);
+    assert_eq!(profile["frequencies"][b'z' as usize], 4);
+}
+
+#[test]
+fn test_checkpoint_resume_mid_file() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("x y\n").unwrap();
+    let big: String = (0..100_000).map(|i| format!("line {i}\n")).collect();
+    temp.child("big.txt").write_str(&big).unwrap();
+    let args = ["--paragraphs", "a.txt", "missing.txt", "big.txt", "a.txt"];
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    let expected = cmd.current_dir(temp.path()).args(args).output().unwrap();
+
+    // saving after every record is slow enough to catch the run part way
+    // through big.txt, as if it were preempted there
+    let checkpoint = temp.child("progress.json");
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("mwc"))
+        .current_dir(temp.path())
+        .args([
+            "--checkpoint",
+            "progress.json",
+            "--checkpoint-interval",
+            "0",
+        ])
+        .args(args)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .unwrap();
+    while !std::fs::read_to_string(checkpoint.path()).is_ok_and(|s| s.contains("\"offset\"")) {
+        assert!(child.try_wait().unwrap().is_none(), "finished too soon");
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["--resume", "progress.json", "a.txt"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("written for other inputs"));
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    let resumed = cmd
+        .current_dir(temp.path())
+        .args(["--resume", "progress.json"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(resumed.stdout).unwrap(),
+        String::from_utf8(expected.stdout).unwrap()
+    );
+    assert_eq!(resumed.stderr, expected.stderr);
+    assert_eq!(resumed.status.code(), expected.status.code());
+}
+
+#[test]
+fn test_checkpoint_removed_when_done() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("x y\n").unwrap();
+    let checkpoint = temp.child("progress.json");
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args([
+            "--checkpoint",
+            "progress.json",
+            "--checkpoint-interval",
+            "0",
+        ])
+        .args(["a.txt", "a.txt"])
+        .assert()
+        .success();
+    checkpoint.assert(predicate::path::missing());
+}
+
+#[test]
+fn test_passthrough() {
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.arg("--passthrough")
+        .write_stdin("one two\nthree\n")
+        .assert()
+        .success()
+        .stdout("one two\nthree\n")
+        .stderr(" 2 3 14\n");
+}
+
+#[test]
+fn test_passthrough_report_file() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let report = temp.child("counts.json");
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args(["--passthrough", "--format", "json", "--report"])
+        .arg(report.path())
+        .write_stdin("one two\n")
+        .assert()
+        .success()
+        .stdout("one two\n")
+        .stderr("");
+    report.assert(predicate::str::contains("\"words\": 2"));
+}
+
+#[test]
+fn test_passthrough_closed_output() {
+    use std::io::Write;
+
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("mwc"))
+        .arg("--passthrough")
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    // nobody reads what's passed through, like `mwc --passthrough | true`
+    drop(child.stdout.take());
+    let mut stdin = child.stdin.take().unwrap();
+    let writer = std::thread::spawn(move || {
+        for _ in 0..10_000 {
+            stdin.write_all(b"some words here\n").unwrap();
+        }
+    });
+    let output = child.wait_with_output().unwrap();
+    writer.join().unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        " 10000 30000 160000\n"
+    );
+}
+
+#[test]
+fn test_fields() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("data.csv")
+        .write_str("id,note\n1,\"a, b\"\n2,\"two\nlines\"\n\n3\n")
+        .unwrap();
+
+    // without quoting, every comma and newline splits
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["-l", "--fields", "data.csv"])
+        .assert()
+        .success()
+        .stdout(" 6 1 3 3 data.csv\n");
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["-l", "--fields", "--rfc4180", "data.csv"])
+        .assert()
+        .success()
+        .stdout(" 6 1 2 1 data.csv\n");
+}
+
+#[test]
+fn test_fields_tsv_json() {
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    let output = cmd
+        .args(["--fields=\\t", "--format", "json"])
+        .write_stdin("a\tb\tc\r\nd\te\tf\r\ng\r\n")
+        .output()
+        .unwrap();
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let fields = &report["files"][0]["fields"];
+
+    assert_eq!(fields["records"], 3);
+    assert_eq!(fields["header_fields"], 3);
+    assert_eq!(fields["min_fields"], 1);
+    assert_eq!(fields["max_fields"], 3);
+    assert_eq!(fields["mismatched"], 1);
+}
+
+#[test]
+fn test_word_chars() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.rs")
+        .write_str("foo_bar(x, y) + 42;\n")
+        .unwrap();
+    temp.child("b.txt")
+        .write_str("naïve café-au-lait\n")
+        .unwrap();
+
+    // POSIX classes are ASCII only, so ï and é split words
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["-w", "--word-chars=[:alnum:]_", "a.rs", "b.txt"])
+        .assert()
+        .success()
+        .stdout(" 4 a.rs\n 5 b.txt\n 9 total\n");
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["-w", "--word-chars=\\p{L}", "a.rs", "b.txt"])
+        .assert()
+        .success()
+        .stdout(" 4 a.rs\n 4 b.txt\n 8 total\n");
+}
+
+#[test]
+fn test_word_regex() {
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args(["-w", "--word-regex", "[A-Za-z_][A-Za-z0-9_]*"])
+        .write_stdin("let x1 = y + 42;\n")
+        .assert()
+        .success()
+        .stdout(" 3\n");
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args(["--word-regex", "(unclosed"])
+        .write_stdin("")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid word definition '(unclosed'",
+        ));
+}
+
+#[test]
+fn test_skip_lines_max_records() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("data.csv")
+        .write_str("name,size\na b,1\nc d e,2\nf,3\n")
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["--skip-lines", "1", "--max-records", "2", "data.csv", "-"])
+        .write_stdin("name,size\na b,1\nc d e,2\nf,3\n")
+        .assert()
+        .success()
+        .stdout(" 2 5 14 data.csv\n 2 5 14 -\n 4 10 28 total\n");
+
+    // passthrough still copies what isn't counted
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args(["--passthrough", "-l", "--max-records", "1"])
+        .write_stdin("one\ntwo\nthree\n")
+        .assert()
+        .success()
+        .stdout("one\ntwo\nthree\n")
+        .stderr(" 1\n");
+}
+
+#[test]
+fn test_bytes_range() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("0123456789\nabc\n").unwrap();
+
+    // the file is seeked into, stdin read through; "ab" is a line of its own
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["-lc", "--bytes-range", "5-13", "a.txt", "-"])
+        .write_stdin("0123456789\nabc\n")
+        .assert()
+        .success()
+        .stdout(" 2 8 a.txt\n 2 8 -\n 4 16 total\n");
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["-lc", "--bytes-range=11-", "a.txt"])
+        .assert()
+        .success()
+        .stdout(" 1 4 a.txt\n");
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.args(["--bytes-range", "9-3"])
+        .write_stdin("")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("END must not come before START"));
+}
+
+#[test]
+fn test_config_precedence() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("config/mpsx/mwc.toml")
+        .write_str("columns = [\"lines\", \"name\"]\nmatch_mode = \"occurrences\"\ncount_matches = [\"a\"]\n")
+        .unwrap();
+    let project = temp.child("project");
+    project
+        .child(".mwc.toml")
+        .write_str("count-matches = \"b\"\nlines = true\n")
+        .unwrap();
+    project
+        .child("sub/x.txt")
+        .write_str("a b b c c c\n")
+        .unwrap();
+    let mwc = || {
+        let mut cmd = Command::cargo_bin("mwc").unwrap();
+        cmd.current_dir(project.child("sub").path())
+            .env("XDG_CONFIG_HOME", temp.child("config").path())
+            .env_remove("MWC_OPTIONS");
+        cmd
+    };
+
+    // the project file's pattern replaces the user's, the rest still applies
+    mwc().arg("x.txt").assert().success().stdout(" 1 2 x.txt\n");
+    mwc()
+        .arg("x.txt")
+        .env("MWC_OPTIONS", "-e c")
+        .assert()
+        .success()
+        .stdout(" 1 3 x.txt\n");
+    mwc()
+        .args(["-e", "a", "x.txt"])
+        .env("MWC_OPTIONS", "-e c")
+        .assert()
+        .success()
+        .stdout(" 1 1 x.txt\n");
+    mwc()
+        .args(["--no-config", "x.txt"])
+        .env("MWC_OPTIONS", "-e c")
+        .assert()
+        .success()
+        .stdout(" 1 6 12 x.txt\n");
+    // any column flag replaces the whole default selection
+    mwc()
+        .args(["-w", "x.txt"])
+        .assert()
+        .success()
+        .stdout(" 6 2 x.txt\n");
+}
+
+#[test]
+fn test_config_conflicts_and_errors() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("one two\n").unwrap();
+    temp.child(".mwc.toml").write_str("gnu = true\n").unwrap();
+
+    // a default that conflicts with the command line is left out
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .env("XDG_CONFIG_HOME", temp.path())
+        .env_remove("MWC_OPTIONS")
+        .args(["--posix", "a.txt"])
+        .assert()
+        .success()
+        .stdout("1 2 8 a.txt\n");
+
+    temp.child(".mwc.toml").write_str("bogus = 1\n").unwrap();
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .env("XDG_CONFIG_HOME", temp.path())
+        .env_remove("MWC_OPTIONS")
+        .arg("a.txt")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            ".mwc.toml: unknown option 'bogus'",
+        ));
+    temp.child(".mwc.toml").write_str("").unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .env("XDG_CONFIG_HOME", temp.path())
+        .env("MWC_OPTIONS", "--format=xml")
+        .args(["--no-config", "a.txt"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    cmd.current_dir(temp.path())
+        .env("XDG_CONFIG_HOME", temp.path())
+        .env("MWC_OPTIONS", "--format=xml")
+        .args(["a.txt"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("MWC_OPTIONS: invalid value 'xml'"));
+}
+
+#[test]
+fn test_print_config() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child(".mwc.toml")
+        .write_str("fields = \"\\\\t\"\nskip-lines = 1\nformat = \"json\"\nmax-lines = 10\n")
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("mwc").unwrap();
+    let output = cmd
+        .current_dir(temp.path())
+        .env("XDG_CONFIG_HOME", temp.path())
+        .env("MWC_OPTIONS", "-lw --grouping")
+        .args(["--print-config", "missing.txt"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.starts_with("# with defaults from "));
+    assert!(output.contains("\n# with defaults from MWC_OPTIONS\n"));
+
+    // what's printed reads back as a config file
+    let settings: toml::Table = output.parse().unwrap();
+    assert_eq!(
+        settings["columns"].as_array().unwrap(),
+        &["lines", "words", "name"].map(toml::Value::from)
+    );
+    assert_eq!(settings["fields"].as_str(), Some("\\t"));
+    assert_eq!(settings["skip_lines"].as_integer(), Some(1));
+    assert_eq!(settings["grouping"].as_bool(), Some(true));
+    assert_eq!(settings["record_separator"].as_str(), Some("\\n"));
+    // as well as the options that don't change the counts
+    assert_eq!(settings["format"].as_str(), Some("json"));
+    assert_eq!(settings["max_lines"].as_str(), Some("10"));
+    assert_eq!(settings["header"].as_bool(), Some(false));
+    assert!(!settings.contains_key("lines"));
+}